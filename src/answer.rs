@@ -0,0 +1,67 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Integer(i128),
+    Text(String),
+    Lines(Vec<String>)
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Answer::Integer(n) => write!(f, "{}", n),
+            Answer::Text(s) => write!(f, "{}", s),
+            Answer::Lines(lines) => write!(f, "{}", lines.join("\n"))
+        };
+    }
+}
+
+macro_rules! integer_answer {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(n: $t) -> Answer {
+                return Answer::Integer(i128::from(n));
+            }
+        })*
+    };
+}
+
+integer_answer!(u8, u16, u32, u64, i8, i16, i32, i64, i128);
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Answer {
+        return Answer::Integer(n as i128);
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Answer {
+        return Answer::Text(s);
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Answer {
+        return Answer::Text(s.to_string());
+    }
+}
+
+impl From<Vec<String>> for Answer {
+    fn from(lines: Vec<String>) -> Answer {
+        return Answer::Lines(lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Answer;
+
+    #[test]
+    fn display() {
+        assert_eq!(Answer::from(u64::MAX).to_string(), "18446744073709551615");
+        assert_eq!(Answer::from(-3_i64).to_string(), "-3");
+        assert_eq!(Answer::from("0042").to_string(), "0042");
+        assert_eq!(Answer::from(vec!["#.#".to_string(), ".#.".to_string()]).to_string(), "#.#\n.#.");
+    }
+}
@@ -0,0 +1,170 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use crate::{Answer, AocError};
+
+const HEADER: &str = "# Known-correct answers, keyed by day and input name.\n";
+
+/// Verified answers stored in a small TOML subset:
+///
+/// ```toml
+/// [day05.my]
+/// part1 = 173706076
+/// part2 = "text answers are quoted"
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct AnswerBook {
+    answers: BTreeMap<(u8, String, u8), Answer>
+}
+
+impl AnswerBook {
+    pub fn load(path: &Path) -> Result<AnswerBook, AocError> {
+        return match fs::read_to_string(path) {
+            Ok(content) => AnswerBook::parse(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(AnswerBook::default()),
+            Err(e) => Err(AocError::Io(e))
+        };
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AocError> {
+        fs::write(path, self.to_toml())?;
+        return Ok(());
+    }
+
+    pub fn parse(content: &str) -> Result<AnswerBook, AocError> {
+        let mut book = AnswerBook::default();
+        let mut section: Option<(u8, String)> = None;
+
+        for (i, raw_line) in content.lines().enumerate() {
+            let line_number = i + 1;
+            let line = raw_line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']')
+                    .ok_or_else(|| AocError::parse(line_number, raw_line.len() + 1, "expected ']'"))?;
+                section = Some(parse_header(header)
+                    .ok_or_else(|| AocError::parse(line_number, 1, "expected '[dayNN.<input>]'"))?);
+                continue;
+            }
+
+            let (day, input) = section.clone()
+                .ok_or_else(|| AocError::parse(line_number, 1, "expected a '[dayNN.<input>]' header before answers"))?;
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| AocError::parse(line_number, 1, "expected 'partN = <answer>'"))?;
+            let part = key.trim().strip_prefix("part")
+                .and_then(|p| p.parse::<u8>().ok())
+                .ok_or_else(|| AocError::parse(line_number, 1, format!("expected 'part1' or 'part2', found '{}'", key.trim())))?;
+            let answer = parse_value(value.trim())
+                .ok_or_else(|| AocError::parse(line_number, raw_line.find('=').unwrap() + 2, "expected an integer or a quoted string"))?;
+
+            book.answers.insert((day, input, part), answer);
+        }
+
+        return Ok(book);
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml = HEADER.to_string();
+        let mut section: Option<(u8, &str)> = None;
+
+        for ((day, input, part), answer) in self.answers.iter() {
+            if section != Some((*day, input)) {
+                toml.push_str(&format!("\n[day{:02}.{}]\n", day, input));
+                section = Some((*day, input));
+            }
+            toml.push_str(&format!("part{} = {}\n", part, format_value(answer)));
+        }
+
+        return toml;
+    }
+
+    pub fn get(&self, day: u8, input: &str, part: u8) -> Option<&Answer> {
+        return self.answers.get(&(day, input.to_string(), part));
+    }
+
+    pub fn record(&mut self, day: u8, input: &str, part: u8, answer: Answer) {
+        self.answers.insert((day, input.to_string(), part), answer);
+    }
+}
+
+fn parse_header(header: &str) -> Option<(u8, String)> {
+    let (day, input) = header.trim().split_once('.')?;
+    let day = day.strip_prefix("day")?.parse::<u8>().ok()?;
+    let valid = !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    return if valid { Some((day, input.to_string())) } else { None };
+}
+
+fn parse_value(value: &str) -> Option<Answer> {
+    if let Ok(n) = value.parse::<i128>() {
+        return Some(Answer::Integer(n));
+    }
+
+    let quoted = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut text = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => text.push('\n'),
+                '"' => text.push('"'),
+                '\\' => text.push('\\'),
+                _ => return None
+            },
+            '"' => return None,
+            _ => text.push(c)
+        }
+    }
+
+    // Quoted values are never integers; `format_value` ends every `Lines` answer with a newline.
+    if text.contains('\n') {
+        let lines = text.strip_suffix('\n').unwrap_or(&text);
+        return Some(Answer::Lines(lines.split('\n').map(|l| l.to_string()).collect()));
+    }
+    return Some(Answer::Text(text));
+}
+
+fn format_value(answer: &Answer) -> String {
+    return match answer {
+        Answer::Integer(n) => n.to_string(),
+        Answer::Text(text) => quote(text),
+        Answer::Lines(lines) => quote(&format!("{}\n", lines.join("\n")))
+    };
+}
+
+fn quote(text: &str) -> String {
+    return format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{answers::AnswerBook, Answer};
+
+    #[test]
+    fn round_trip() {
+        let mut book = AnswerBook::default();
+        book.record(5, "my", 1, Answer::from(173706076_u64));
+        book.record(1, "sample-part2", 2, Answer::from(281_u64));
+        book.record(10, "my", 2, Answer::from(vec!["#\"#".to_string(), ".\\.".to_string()]));
+        book.record(10, "sample", 1, Answer::from(vec!["#.#".to_string()]));
+        book.record(10, "sample", 2, Answer::from("0042"));
+        book.record(11, "my", 1, Answer::from("-7"));
+
+        let toml = book.to_toml();
+        assert_eq!(AnswerBook::parse(&toml).unwrap(), book);
+        assert!(toml.contains("[day05.my]\npart1 = 173706076\n"));
+    }
+
+    #[test]
+    fn invalid_value() {
+        let error = AnswerBook::parse("[day01.my]\npart1 = oops\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 8: expected an integer or a quoted string");
+    }
+
+    #[test]
+    fn answer_outside_section() {
+        assert!(AnswerBook::parse("part1 = 1\n").is_err());
+    }
+}
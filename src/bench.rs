@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use crate::{AocError, Solution};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub day: u8,
+    pub part: u8,
+    pub iterations: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration
+}
+
+/// Solves `part` on an in-memory `input` `iterations` times.
+pub fn bench(solution: &dyn Solution, part: u8, input: &[u8], iterations: usize) -> Result<BenchResult, AocError> {
+    if iterations == 0 {
+        return Err(AocError::InvalidValue("at least one iteration is needed".to_string()));
+    }
+
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        solution.solve(part, &mut &input[..])?;
+        samples.push(start.elapsed());
+    }
+    samples.sort();
+
+    return Ok(BenchResult {
+        day: solution.day(),
+        part,
+        iterations,
+        min: samples[0],
+        median: percentile(&samples, 50),
+        p95: percentile(&samples, 95)
+    });
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`.
+fn percentile(samples: &[Duration], p: usize) -> Duration {
+    let rank = (p * samples.len()).div_ceil(100).max(1);
+    return samples[rank - 1];
+}
+
+pub fn to_json(results: &[BenchResult]) -> String {
+    let entries: Vec<String> = results.iter()
+        .map(|r| format!(
+            "    {{\"day\": {}, \"part\": {}, \"iterations\": {}, \"min_ns\": {}, \"median_ns\": {}, \"p95_ns\": {}}}",
+            r.day, r.part, r.iterations, r.min.as_nanos(), r.median.as_nanos(), r.p95.as_nanos()))
+        .collect();
+    return format!("{{\n  \"results\": [\n{}\n  ]\n}}\n", entries.join(",\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{bench::{bench, percentile, to_json, BenchResult}, day04::Day04};
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let samples: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&samples, 50), Duration::from_millis(10));
+        assert_eq!(percentile(&samples, 95), Duration::from_millis(19));
+        assert_eq!(percentile(&samples[..1], 95), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn bench_sample() {
+        let result = bench(&Day04, 2, "Card 1: 41 48 | 83 86\n".as_bytes(), 5).unwrap();
+        assert_eq!((result.day, result.part, result.iterations), (4, 2, 5));
+        assert!(result.min <= result.median && result.median <= result.p95);
+    }
+
+    #[test]
+    fn json() {
+        let result = BenchResult {
+            day: 5,
+            part: 2,
+            iterations: 3,
+            min: Duration::from_nanos(10),
+            median: Duration::from_nanos(20),
+            p95: Duration::from_nanos(30)
+        };
+        assert_eq!(to_json(&[result]), "{\n  \"results\": [\n    {\"day\": 5, \"part\": 2, \"iterations\": 3, \"min_ns\": 10, \"median_ns\": 20, \"p95_ns\": 30}\n  ]\n}\n");
+    }
+}
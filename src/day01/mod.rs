@@ -1,33 +1,81 @@
 use std::{io::BufRead, collections::HashMap};
 
-pub fn what_is_the_sum_of_all_of_the_calibration_values(input: &mut dyn BufRead) -> u32 {
-    return read_input(input).iter()
-        .map(|s| recover_calibration_value(s))
-        .sum();
+use crate::{checked_sum, downcast, read_input, Answer, AocError, Model, Solution};
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    fn day(&self) -> u8 {
+        return 1;
+    }
+
+    fn title(&self) -> &'static str {
+        return "Trebuchet?!";
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError> {
+        return Ok(Box::new(parse(input)?));
+    }
+
+    fn part1(&self, model: &Model) -> Result<Answer, AocError> {
+        return part1(downcast::<CalibrationDocument>(model)?).map(Answer::from);
+    }
+
+    fn part2(&self, model: &Model) -> Result<Answer, AocError> {
+        return part2(downcast::<CalibrationDocument>(model)?).map(Answer::from);
+    }
+}
+
+pub struct CalibrationDocument {
+    pub lines: Vec<String>
+}
+
+pub fn parse(input: &mut dyn BufRead) -> Result<CalibrationDocument, AocError> {
+    return Ok(CalibrationDocument {
+        lines: read_input(input)?
+    });
+}
+
+pub fn part1(document: &CalibrationDocument) -> Result<u64, AocError> {
+    let values = document.lines.iter()
+        .enumerate()
+        .map(|(i, s)| recover_calibration_value(s).map(u64::from).ok_or_else(|| no_digit_error(i + 1)))
+        .collect::<Result<Vec<u64>, AocError>>()?;
+    return checked_sum(values, "sum of calibration values");
+}
+
+pub fn part2(document: &CalibrationDocument) -> Result<u64, AocError> {
+    let values = document.lines.iter()
+        .enumerate()
+        .map(|(i, s)| recover_real_calibration_value(s).map(u64::from).ok_or_else(|| no_digit_error(i + 1)))
+        .collect::<Result<Vec<u64>, AocError>>()?;
+    return checked_sum(values, "sum of calibration values");
+}
+
+pub fn what_is_the_sum_of_all_of_the_calibration_values(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part1(&parse(input)?);
 }
 
-pub fn what_is_the_real_sum_of_all_of_the_calibration_values(input: &mut dyn BufRead) -> u32 {
-    return read_input(input).iter()
-        .map(|s| recover_real_calibration_value(s))
-        .sum();
+pub fn what_is_the_real_sum_of_all_of_the_calibration_values(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part2(&parse(input)?);
 }
 
-fn read_input(input: &mut dyn BufRead) -> Vec<String> {
-    return input.lines().map(|line| line.unwrap()).collect::<Vec<String>>();
+fn no_digit_error(line: usize) -> AocError {
+    return AocError::parse(line, 1, "expected at least one digit");
 }
 
-fn recover_calibration_value(line: &String) -> u32 {
-    let mut iterator = line.chars().into_iter()
-        .filter(|&c| c.is_digit(10))
+fn recover_calibration_value(line: &str) -> Option<u32> {
+    let mut iterator = line.chars()
+        .filter(|&c| c.is_ascii_digit())
         .map(|c| c.to_digit(10).unwrap());
 
-    let first = iterator.next().unwrap();
-    let last = iterator.last().unwrap_or(first);
+    let first = iterator.next()?;
+    let last = iterator.next_back().unwrap_or(first);
     
-    return first * 10 + last;
+    return Some(first * 10 + last);
 }
 
-fn recover_real_calibration_value(line: &String) -> u32 {
+fn recover_real_calibration_value(line: &str) -> Option<u32> {
     let digits_spelled_out_with_letters: HashMap<&str, char> = HashMap::from([
         ("one", '1'),
         ("two", '2'),
@@ -40,16 +88,16 @@ fn recover_real_calibration_value(line: &String) -> u32 {
         ("nine", '9')
     ]);
 
-    let mut unified_line: String = line.clone();
+    let mut unified_line: String = line.to_string();
 
     let first_spelled_as_letter = digits_spelled_out_with_letters.iter()
-        .map(|(&k, _)| (unified_line.find(&k), k))
+        .map(|(&k, _)| (unified_line.find(k), k))
         .filter(|(pos, _)| pos.is_some())
         .map(|(pos, k)| (pos.unwrap(), k))
         .min_by_key(|(pos, _)| *pos);
 
-    let first_digit_pos = unified_line.chars().into_iter().enumerate()
-        .filter(|(_, c)| c.is_digit(10))
+    let first_digit_pos = unified_line.chars().enumerate()
+        .filter(|(_, c)| c.is_ascii_digit())
         .map(|(pos, _)| pos)
         .next();
 
@@ -65,8 +113,8 @@ fn recover_real_calibration_value(line: &String) -> u32 {
         .map(|(pos, k)| (pos.unwrap(), k))
         .max_by_key(|(pos, _)| *pos);
 
-    let last_digit_pos = unified_line.chars().into_iter().enumerate()
-        .filter(|(_, c)| c.is_digit(10))
+    let last_digit_pos = unified_line.chars().enumerate()
+        .filter(|(_, c)| c.is_ascii_digit())
         .map(|(pos, _ )| pos)
         .last();
 
@@ -76,65 +124,71 @@ fn recover_real_calibration_value(line: &String) -> u32 {
         }
     }
 
-    let mut iterator = unified_line.chars().into_iter()
-        .filter(|c| c.is_digit(10))
+    let mut iterator = unified_line.chars()
+        .filter(|c| c.is_ascii_digit())
         .map(|c| c.to_digit(10).unwrap());
 
-    let first = iterator.next().unwrap();
-    let last = iterator.last().unwrap_or(first);
+    let first = iterator.next()?;
+    let last = iterator.next_back().unwrap_or(first);
 
-    return first * 10 + last;
+    return Some(first * 10 + last);
 }
 
 
 #[cfg(test)]
 mod tests {
     use crate::day01::{what_is_the_sum_of_all_of_the_calibration_values, what_is_the_real_sum_of_all_of_the_calibration_values};
-    use std::{fs::File, io::BufReader};
+    use crate::input::open;
 
     #[test]
     fn sample_part1_input() {
-        let mut f = BufReader::new(File::open("./src/day01/sample-part1.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_calibration_values(&mut f), 142);
+        let mut f = open(1, "sample-part1").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_calibration_values(&mut f).unwrap(), 142);
     }
 
     #[test]
     fn part1_input() {
-        let mut f = BufReader::new(File::open("./src/day01/my.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_calibration_values(&mut f), 54573);
+        let mut f = open(1, "my").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_calibration_values(&mut f).unwrap(), 54573);
     }
 
     #[test]
     fn part2_samples() {
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "two1nine".as_bytes()), 29);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "eightwothree".as_bytes()), 83);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "abcone2threexyz".as_bytes()), 13);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "xtwone3four".as_bytes()), 24);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "two1nine".as_bytes()).unwrap(), 29);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "eightwothree".as_bytes()).unwrap(), 83);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "abcone2threexyz".as_bytes()).unwrap(), 13);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "xtwone3four".as_bytes()).unwrap(), 24);
 
-        let mut f = BufReader::new(File::open("./src/day01/sample-part2.input").unwrap());
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut f), 281);
+        let mut f = open(1, "sample-part2").unwrap();
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut f).unwrap(), 281);
     }
 
     #[test]
     fn part2_extra_samples() {
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "fourzvmlt3sggpjzssljc8twoeighttwo".as_bytes()), 42);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight".as_bytes()), 11);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "1oneight".as_bytes()), 18);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight8".as_bytes()), 18);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight9".as_bytes()), 19);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "3oneight9".as_bytes()), 39);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight39".as_bytes()), 19);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "39oneight".as_bytes()), 38);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneightwo".as_bytes()), 12);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneightwo3".as_bytes()), 13);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "3oneightwo".as_bytes()), 32);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "fiveeight2zxjpzffvdsevenjhjvjfiveone".as_bytes()), 51);
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "1eight5eight".as_bytes()), 18);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "fourzvmlt3sggpjzssljc8twoeighttwo".as_bytes()).unwrap(), 42);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight".as_bytes()).unwrap(), 11);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "1oneight".as_bytes()).unwrap(), 18);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight8".as_bytes()).unwrap(), 18);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight9".as_bytes()).unwrap(), 19);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "3oneight9".as_bytes()).unwrap(), 39);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneight39".as_bytes()).unwrap(), 19);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "39oneight".as_bytes()).unwrap(), 38);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneightwo".as_bytes()).unwrap(), 12);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "oneightwo3".as_bytes()).unwrap(), 13);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "3oneightwo".as_bytes()).unwrap(), 32);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "fiveeight2zxjpzffvdsevenjhjvjfiveone".as_bytes()).unwrap(), 51);
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut "1eight5eight".as_bytes()).unwrap(), 18);
     }
 
     #[test]
     fn part2_input() {
-        let mut f = BufReader::new(File::open("./src/day01/my.input").unwrap());
-        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut f), 54591);
+        let mut f = open(1, "my").unwrap();
+        assert_eq!(what_is_the_real_sum_of_all_of_the_calibration_values(&mut f).unwrap(), 54591);
+    }
+
+    #[test]
+    fn line_without_digits() {
+        let error = what_is_the_sum_of_all_of_the_calibration_values(&mut "1abc2\nabc".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 1: expected at least one digit");
     }
 }
@@ -1,97 +1,158 @@
 use std::{io::BufRead, collections::HashMap};
 use regex::Regex;
 
-use crate::read_input;
-
-pub fn what_is_the_sum_of_the_ids_of_possible_games(input: &mut dyn BufRead) -> u32 {
-    return read_input(input).iter()
-        .map(|s| parse_game(s))
-        .map(|(id, subsets)| (id, is_game_possible(subsets)))
-        .filter(|(_, possible)| *possible)
-        .map(|(id, _)| id)
-        .sum();
+use crate::{checked_sum, downcast, read_input, Answer, AocError, Model, Solution, error::{column_of, parse_number}};
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    fn day(&self) -> u8 {
+        return 2;
+    }
+
+    fn title(&self) -> &'static str {
+        return "Cube Conundrum";
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError> {
+        return Ok(Box::new(parse(input)?));
+    }
+
+    fn part1(&self, model: &Model) -> Result<Answer, AocError> {
+        return part1(downcast::<Vec<Game>>(model)?).map(Answer::from);
+    }
+
+    fn part2(&self, model: &Model) -> Result<Answer, AocError> {
+        return part2(downcast::<Vec<Game>>(model)?).map(Answer::from);
+    }
 }
 
-pub fn what_is_the_sum_of_the_power_of_sets(input: &mut dyn BufRead) -> u32 {
-    return read_input(input).iter()
-        .map(|s| parse_game(s))
-        .map(|(_, subsets)| get_power(subsets))
-        .sum();
+pub type CubeSet = HashMap<String, u32>;
+
+pub struct Game {
+    pub id: u32,
+    pub subsets: Vec<CubeSet>
 }
 
-fn parse_game(line: &String) -> (u32, Vec<HashMap<&str, u32>>) {
-    let re = Regex::new(r"Game (\d+): (.*)").unwrap();
-    let captures = re.captures(line).unwrap();
-    let game_id = captures.get(1).unwrap().as_str().parse::<u32>().unwrap();
-    let subsets = captures.get(2).unwrap().as_str().split(';')
-        .map(|s| parse_subset(s));
-    return (game_id, subsets.collect());
+pub fn parse(input: &mut dyn BufRead) -> Result<Vec<Game>, AocError> {
+    return read_input(input)?.iter()
+        .enumerate()
+        .map(|(i, s)| parse_game(i + 1, s))
+        .collect();
 }
 
-fn parse_subset(raw_subset: &str) -> HashMap<&str, u32> {
-    return raw_subset.split(',')
-        .map(|raw_cubes| parse_cubes(raw_cubes))
-        .into_iter().collect();
+pub fn part1(games: &[Game]) -> Result<u64, AocError> {
+    let ids = games.iter()
+        .filter(|game| game.is_possible())
+        .map(|game| u64::from(game.id));
+    return checked_sum(ids, "sum of game ids");
 }
 
-fn parse_cubes(raw_cubes: &str) -> (&str, u32) {
-    let re = Regex::new(r"(\d+) (.*)").unwrap();
-    let captures = re.captures(raw_cubes).unwrap();
-    return (captures.get(2).unwrap().as_str(), captures.get(1).unwrap().as_str().parse::<u32>().unwrap())
+pub fn part2(games: &[Game]) -> Result<u64, AocError> {
+    let powers = games.iter()
+        .map(|game| game.power())
+        .collect::<Result<Vec<u64>, AocError>>()?;
+    return checked_sum(powers, "sum of powers");
 }
 
-fn is_game_possible(cube_subsets: Vec<HashMap<&str, u32>>) -> bool {
-    return cube_subsets.iter()
-        .all(|subset| is_subset_possible(subset));
+pub fn what_is_the_sum_of_the_ids_of_possible_games(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part1(&parse(input)?);
 }
 
-fn is_subset_possible(subset: &HashMap<&str, u32>) -> bool {
-    return subset.get("red").map_or(true, |&x| x <= 12)
-        && subset.get("green").map_or(true, |&x| x <= 13)
-        && subset.get("blue").map_or(true, |&x| x <= 14);
+pub fn what_is_the_sum_of_the_power_of_sets(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part2(&parse(input)?);
 }
 
-fn get_power(cube_subsets: Vec<HashMap<&str, u32>>) -> u32 {
-    let rgb: Vec<(u32, u32, u32)> = cube_subsets.iter()
-        .map(|c| (
-            c.get("red").map(|v| *v).unwrap_or(0), 
-            c.get("green").map(|v| *v).unwrap_or(0), 
-            c.get("blue").map(|v| *v).unwrap_or(0)
-        ))
+fn parse_game(line_number: usize, line: &str) -> Result<Game, AocError> {
+    let re = Regex::new(r"Game (\d+): (.*)").unwrap();
+    let captures = re.captures(line)
+        .ok_or_else(|| AocError::parse(line_number, 1, "expected 'Game <id>: '"))?;
+    let id = parse_number(line_number, line, captures.get(1).unwrap().as_str())?;
+    let subsets = captures.get(2).unwrap().as_str().split(';')
+        .map(|s| parse_subset(line_number, line, s))
+        .collect::<Result<Vec<CubeSet>, AocError>>()?;
+    return Ok(Game {
+        id,
+        subsets
+    });
+}
+
+fn parse_subset(line_number: usize, line: &str, raw_subset: &str) -> Result<CubeSet, AocError> {
+    return raw_subset.split(',')
+        .map(|raw_cubes| parse_cubes(line_number, line, raw_cubes))
         .collect();
+}
+
+fn parse_cubes(line_number: usize, line: &str, raw_cubes: &str) -> Result<(String, u32), AocError> {
+    let re = Regex::new(r"(\d+) (.*)").unwrap();
+    let captures = re.captures(raw_cubes)
+        .ok_or_else(|| AocError::parse(line_number, column_of(line, raw_cubes.trim_start()), "expected '<count> <color>'"))?;
+    return Ok((captures.get(2).unwrap().as_str().to_string(), parse_number(line_number, line, captures.get(1).unwrap().as_str())?));
+}
+
+impl Game {
+    pub fn is_possible(&self) -> bool {
+        return self.subsets.iter()
+            .all(is_subset_possible);
+    }
 
-    let max_r = rgb.iter().max_by_key(|c| c.0).map(|c| c.0).unwrap_or(0);
-    let max_g = rgb.iter().max_by_key(|c| c.1).map(|c| c.1).unwrap_or(0);
-    let max_b = rgb.iter().max_by_key(|c| c.2).map(|c| c.2).unwrap_or(0);
+    pub fn power(&self) -> Result<u64, AocError> {
+        let rgb: Vec<(u32, u32, u32)> = self.subsets.iter()
+            .map(|c| (
+                c.get("red").copied().unwrap_or(0), 
+                c.get("green").copied().unwrap_or(0), 
+                c.get("blue").copied().unwrap_or(0)
+            ))
+            .collect();
+
+        let max_r = rgb.iter().max_by_key(|c| c.0).map(|c| c.0).unwrap_or(0);
+        let max_g = rgb.iter().max_by_key(|c| c.1).map(|c| c.1).unwrap_or(0);
+        let max_b = rgb.iter().max_by_key(|c| c.2).map(|c| c.2).unwrap_or(0);
+
+        return u64::from(max_r).checked_mul(u64::from(max_g))
+            .and_then(|rg| rg.checked_mul(u64::from(max_b)))
+            .ok_or_else(|| AocError::InvalidValue(format!("power of {} red, {} green and {} blue cubes overflows u64", max_r, max_g, max_b)));
+    }
+}
 
-    return max_r * max_g * max_b;
+fn is_subset_possible(subset: &CubeSet) -> bool {
+    return subset.get("red").is_none_or(|&x| x <= 12)
+        && subset.get("green").is_none_or(|&x| x <= 13)
+        && subset.get("blue").is_none_or(|&x| x <= 14);
 }
 
+#[cfg(test)]
 mod tests {
     use crate::day02::{what_is_the_sum_of_the_ids_of_possible_games, what_is_the_sum_of_the_power_of_sets};
-    use std::{fs::File, io::BufReader};
+    use crate::input::open;
 
     #[test]
     fn sample_part1_input() {
-        let mut f = BufReader::new(File::open("./src/day02/sample.input").unwrap());
-        assert_eq!(what_is_the_sum_of_the_ids_of_possible_games(&mut f), 8);
+        let mut f = open(2, "sample").unwrap();
+        assert_eq!(what_is_the_sum_of_the_ids_of_possible_games(&mut f).unwrap(), 8);
     }
 
     #[test]
     fn part1_input() {
-        let mut f = BufReader::new(File::open("./src/day02/my.input").unwrap());
-        assert_eq!(what_is_the_sum_of_the_ids_of_possible_games(&mut f), 1734);
+        let mut f = open(2, "my").unwrap();
+        assert_eq!(what_is_the_sum_of_the_ids_of_possible_games(&mut f).unwrap(), 1734);
     }
 
     #[test]
     fn sample_part2_input() {
-        let mut f = BufReader::new(File::open("./src/day02/sample.input").unwrap());
-        assert_eq!(what_is_the_sum_of_the_power_of_sets(&mut f), 2286);
+        let mut f = open(2, "sample").unwrap();
+        assert_eq!(what_is_the_sum_of_the_power_of_sets(&mut f).unwrap(), 2286);
     }
 
     #[test]
     fn part2_input() {
-        let mut f = BufReader::new(File::open("./src/day02/my.input").unwrap());
-        assert_eq!(what_is_the_sum_of_the_power_of_sets(&mut f), 70387);
+        let mut f = open(2, "my").unwrap();
+        assert_eq!(what_is_the_sum_of_the_power_of_sets(&mut f).unwrap(), 70387);
+    }
+
+    #[test]
+    fn malformed_cubes() {
+        let error = what_is_the_sum_of_the_ids_of_possible_games(&mut "Game 1: 3 blue, red".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 17: expected '<count> <color>'");
     }
 }
@@ -1,169 +1,383 @@
-use std::io::BufRead;
+use std::{collections::HashMap, fmt, io::BufRead};
 
-use crate::read_input;
+use crate::{checked_sum, downcast, grid::{Coord, Grid}, Answer, AocError, Model, Solution};
 
-pub fn what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(input: &mut dyn BufRead) -> u32 {
-    let schematic = parse_schematic(input);
-    
-    return schematic.get_numbers().iter()
+mod report;
+
+pub use report::{report, Report, SymbolReport};
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    fn day(&self) -> u8 {
+        return 3;
+    }
+
+    fn title(&self) -> &'static str {
+        return "Gear Ratios";
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError> {
+        return Ok(Box::new(parse(input)?));
+    }
+
+    fn part1(&self, model: &Model) -> Result<Answer, AocError> {
+        return part1(downcast::<Schematic>(model)?).map(Answer::from);
+    }
+
+    fn part2(&self, model: &Model) -> Result<Answer, AocError> {
+        return part2(downcast::<Schematic>(model)?).map(Answer::from);
+    }
+}
+
+pub fn part1(schematic: &Schematic) -> Result<u64, AocError> {
+    let part_numbers = schematic.get_numbers().iter()
         .filter(|&n| schematic.is_part_number(n))
-        .map(|pn| schematic.to_number(pn))
-        .sum();
+        .map(|n| n.value);
+    return checked_sum(part_numbers, "sum of part numbers");
+}
+
+pub fn part2(schematic: &Schematic) -> Result<u64, AocError> {
+    let gear_ratios = schematic.gears().iter()
+        .map(|(_, numbers)| gear_ratio(numbers))
+        .collect::<Result<Vec<u64>, AocError>>()?;
+    return checked_sum(gear_ratios, "sum of gear ratios");
+}
+
+pub fn what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part1(&parse(input)?);
+}
+
+pub fn what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part2(&parse(input)?);
+}
+
+fn gear_ratio(numbers: &[PartNumber]) -> Result<u64, AocError> {
+    return numbers.iter()
+        .try_fold(1_u64, |acc, n| acc.checked_mul(n.value)
+            .ok_or_else(|| AocError::InvalidValue("gear ratio overflows u64".to_string())));
 }
 
-pub fn what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(input: &mut dyn BufRead) -> u32 {
-    let schematic = parse_schematic(input);
+pub fn parse(input: &mut dyn BufRead) -> Result<Schematic, AocError> {
+    return Schematic::new(Grid::parse(input)?);
+}
+
+pub fn parse_with_rules(input: &mut dyn BufRead, rules: SymbolRules) -> Result<Schematic, AocError> {
+    return Schematic::with_rules(Grid::parse(input)?, rules);
+}
 
-    return schematic.find('*').iter()
-        .map(|c| schematic.get_adjacent_numbers(c))
-        .filter(|numbers| numbers.len() == 2)
-        .map(|numbers| numbers.iter().map(|n| schematic.to_number(n)).fold(1, |acc, elem| acc * elem))
-        .sum();
+/// Decides what each character of a schematic means. Digits always belong to numbers.
+#[derive(Debug, Clone)]
+pub struct SymbolRules {
+    empty: Vec<char>,
+    /// `None` makes every character that is neither a digit nor empty a symbol.
+    symbols: Option<Vec<char>>,
+    /// Symbols that are gears when exactly `arity` numbers touch them.
+    gears: Vec<(char, usize)>
+}
+
+impl Default for SymbolRules {
+    /// The puzzle's rules: `.` is empty, anything else is a symbol, and `*` next to exactly
+    /// two numbers is a gear.
+    fn default() -> SymbolRules {
+        return SymbolRules {
+            empty: vec!['.'],
+            symbols: None,
+            gears: vec![('*', 2)]
+        };
+    }
 }
 
-fn parse_schematic(input: &mut dyn BufRead) -> Schematic {
-    let grid = read_input(input).iter()
-        .map(|line| line.chars().into_iter().collect::<Vec<char>>())
-        .collect();
-    return Schematic {
-        grid
+impl SymbolRules {
+    pub fn with_empty(mut self, empty: &[char]) -> SymbolRules {
+        self.empty = empty.to_vec();
+        return self;
+    }
+
+    /// Only `symbols` count as symbols, other characters are ignored like empty ones.
+    pub fn with_symbols(mut self, symbols: &[char]) -> SymbolRules {
+        self.symbols = Some(symbols.to_vec());
+        return self;
+    }
+
+    /// Replaces the gear rules with one per `(symbol, arity)` pair.
+    pub fn with_gears(mut self, gears: &[(char, usize)]) -> SymbolRules {
+        self.gears = gears.to_vec();
+        return self;
+    }
+
+    pub fn is_empty(&self, c: char) -> bool {
+        return self.empty.contains(&c);
+    }
+
+    pub fn is_symbol(&self, c: char) -> bool {
+        return !c.is_ascii_digit() && !self.is_empty(c)
+            && self.symbols.as_ref().is_none_or(|symbols| symbols.contains(&c));
+    }
+
+    /// How many numbers must touch `c` for it to be a gear, if it can be one at all.
+    pub fn gear_arity(&self, c: char) -> Option<usize> {
+        return self.gears.iter()
+            .find(|(symbol, _)| *symbol == c)
+            .map(|(_, arity)| *arity)
+            .filter(|_| self.is_symbol(c));
     }
 }
 
-struct Schematic {
-    grid: Vec<Vec<char>>
+/// A run of digits on one row, covering columns `col_start..col_end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PartNumber {
+    pub row: usize,
+    pub col_start: usize,
+    pub col_end: usize,
+    pub value: u64
 }
 
-impl Schematic {    
-    fn get_numbers(&self) -> Vec<Vec<(usize, usize)>> {
-        return self.grid.iter()
-            .zip(0..self.grid.len())
-            .flat_map(|(row, y)| self.find_numbers(y, row))
-            .collect();
+impl PartNumber {
+    /// The top left and bottom right corners, inclusive, of the number and the cells
+    /// around it. The box can reach past the right and bottom edges of the schematic.
+    pub fn bounding_box(&self) -> (Coord, Coord) {
+        return ((self.col_start.saturating_sub(1), self.row.saturating_sub(1)), (self.col_end, self.row + 1));
     }
 
-    fn find_numbers(&self, y: usize, row: &Vec<char>) -> Vec<Vec<(usize, usize)>> {
-        let mut numbers: Vec<Vec<(usize, usize)>> = Vec::new();
-        let mut it = row.iter().zip(0..row.len());
+    /// The cells the number covers.
+    pub fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
+        return (self.col_start..self.col_end).map(|x| (x, self.row));
+    }
 
-        loop {
-            let number: Vec<(usize, usize)> = it.by_ref()
-                .skip_while(|x| !x.0.is_digit(10))
-                .take_while(|x| x.0.is_digit(10))
-                .map(|x| (x.1, y))
-                .collect();
+    /// The cells around the number, in reading order, that are inside a schematic of
+    /// `width` columns and `height` rows.
+    pub fn neighbours(&self, width: usize, height: usize) -> impl Iterator<Item = Coord> + '_ {
+        let ((left, top), (right, bottom)) = self.bounding_box();
+        return (top..=bottom.min(height.saturating_sub(1)))
+            .flat_map(move |y| (left..=right.min(width.saturating_sub(1))).map(move |x| (x, y)))
+            .filter(|&(x, y)| y != self.row || x < self.col_start || x >= self.col_end);
+    }
+}
 
-            if number.len() == 0 {
-                break;
-            }
-            else {
-                numbers.push(number);
+impl fmt::Display for PartNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} at row {}, columns {}..{}", self.value, self.row, self.col_start, self.col_end);
+    }
+}
+
+pub struct Schematic {
+    grid: Grid<char>,
+    rules: SymbolRules,
+    index: Index
+}
+
+/// Where every number is and which numbers touch each symbol, built once per schematic
+/// so lookups do not rescan the grid.
+struct Index {
+    numbers: Vec<PartNumber>,
+    /// For every cell, the position in `numbers` of the number covering it.
+    number_at: Grid<Option<usize>>,
+    /// For every symbol, the positions in `numbers` of the numbers around it.
+    adjacent: HashMap<Coord, Vec<usize>>
+}
+
+impl Schematic {
+    pub fn new(grid: Grid<char>) -> Result<Schematic, AocError> {
+        return Schematic::with_rules(grid, SymbolRules::default());
+    }
+
+    pub fn with_rules(grid: Grid<char>, rules: SymbolRules) -> Result<Schematic, AocError> {
+        let numbers: Vec<PartNumber> = grid.rows()
+            .enumerate()
+            .map(|(y, row)| Schematic::find_numbers(y, row))
+            .collect::<Result<Vec<Vec<PartNumber>>, AocError>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut number_at = grid.map(|_| None);
+        for (i, number) in numbers.iter().enumerate() {
+            for c in number.coords() {
+                *number_at.get_mut(c).unwrap() = Some(i);
             }
         }
 
-        return numbers;
-    }
+        let mut schematic = Schematic {
+            grid,
+            rules,
+            index: Index { numbers, number_at, adjacent: HashMap::new() }
+        };
+        schematic.index.adjacent = schematic.grid.coords()
+            .filter(|c| schematic.is_part(c))
+            .map(|c| (c, schematic.numbers_around(&c)))
+            .collect();
 
-    fn is_part_number(&self, number: &Vec<(usize, usize)>) -> bool {
-        return number.iter().any(|c| self.is_adjacent_to_part(c));
+        return Ok(schematic);
     }
 
-    fn is_adjacent_to_part(&self, coord: &(usize, usize)) -> bool {
-        return self.get_adjacent_coords(coord).iter().any(|c| self.is_part(c));
+    pub fn get_numbers(&self) -> &[PartNumber] {
+        return &self.index.numbers;
     }
 
-    fn is_part(&self, coord: &(usize, usize)) -> bool {
-        let field = self.get(coord);
-        return !field.is_digit(10) && *field != '.';
+    fn find_numbers(y: usize, row: &[char]) -> Result<Vec<PartNumber>, AocError> {
+        let mut numbers: Vec<PartNumber> = Vec::new();
+        let mut it = row.iter().enumerate().peekable();
+
+        while let Some((x, c)) = it.next() {
+            let Some(digit) = c.to_digit(10) else {
+                continue;
+            };
+
+            let mut number = PartNumber { row: y, col_start: x, col_end: x + 1, value: u64::from(digit) };
+            while let Some((_, digit)) = it.next_if(|(_, c)| c.is_ascii_digit()) {
+                number.value = number.value.checked_mul(10)
+                    .and_then(|n| n.checked_add(u64::from(digit.to_digit(10).unwrap())))
+                    .ok_or_else(|| AocError::InvalidValue(format!("number at line {}, column {} overflows u64", y + 1, x + 1)))?;
+                number.col_end += 1;
+            }
+            numbers.push(number);
+        }
+
+        return Ok(numbers);
     }
 
-    fn get(&self, coord: &(usize, usize)) -> &char {
-        return self.grid.get(coord.1).unwrap().get(coord.0).unwrap();
+    pub fn is_part_number(&self, number: &PartNumber) -> bool {
+        return number.neighbours(self.grid.width(), self.grid.height())
+            .any(|c| self.is_part(&c));
     }
 
-    fn get_adjacent_coords(&self, coord: &(usize, usize)) -> Vec<(usize, usize)> {
-        let cc = (coord.0 as isize, coord.1 as isize);
+    fn is_part(&self, coord: &Coord) -> bool {
+        return self.rules.is_symbol(*self.get(coord));
+    }
 
-        return vec![
-            (cc.0 - 1, cc.1),
-            (cc.0 - 1, cc.1 - 1),
-            (cc.0, cc.1 - 1),
-            (cc.0 - 1, cc.1 + 1),
-            (cc.0 + 1, cc.1),
-            (cc.0 + 1, cc.1 - 1),
-            (cc.0, cc.1 + 1),
-            (cc.0 + 1, cc.1 + 1),
-        ].iter()
-            .filter(|&c| self.in_grid(c))
-            .map(|c| (c.0 as usize, c.1 as usize))
-            .collect::<Vec<(usize, usize)>>();
+    pub fn rules(&self) -> &SymbolRules {
+        return &self.rules;
     }
 
-    fn in_grid(&self, coord: &(isize, isize)) -> bool {
-        return coord.1 >= 0 && coord.1 < self.grid.len() as isize 
-            && coord.0 >= 0 && coord.0 < self.grid.get(coord.1 as usize).unwrap().len() as isize;
+    /// Every gear, in reading order, with the numbers around it.
+    pub fn gears(&self) -> Vec<(Coord, Vec<PartNumber>)> {
+        return self.grid.coords()
+            .filter_map(|c| self.rules.gear_arity(*self.get(&c)).map(|arity| (c, arity)))
+            .map(|(c, arity)| (c, self.get_adjacent_numbers(&c), arity))
+            .filter(|(_, numbers, arity)| numbers.len() == *arity)
+            .map(|(c, numbers, _)| (c, numbers))
+            .collect();
     }
 
-    fn to_number(&self, number: &Vec<(usize, usize)>) -> u32 {
-        return number.iter()
-            .map(|c| self.get(c).to_digit(10).unwrap())
-            .fold(0, |acc, elem| acc * 10 + elem);
+    fn get(&self, coord: &Coord) -> &char {
+        return self.grid.get(*coord).unwrap();
     }
 
-    fn find(&self, symbol: char) -> Vec<(usize, usize)> {
-        return self.coords().iter()
+    pub fn find(&self, symbol: char) -> Vec<Coord> {
+        return self.grid.coords()
             .filter(|c| *self.get(c) == symbol)
-            .map(|&c| c)
             .collect();
     }
 
-    fn coords(&self) -> Vec<(usize, usize)> {
-        return self.grid.iter()
-            .zip(0..self.grid.len())
-            .flat_map(|(row, y)| row.iter().zip(0..row.len()).map(move |(_, x)| (x, y)))
+    pub fn get_adjacent_numbers(&self, coord: &Coord) -> Vec<PartNumber> {
+        let adjacent = match self.index.adjacent.get(coord) {
+            Some(adjacent) => adjacent.clone(),
+            None => self.numbers_around(coord)
+        };
+        return adjacent.into_iter()
+            .map(|i| self.index.numbers[i])
             .collect();
     }
 
-    fn get_adjacent_numbers(&self, coord: &(usize, usize)) -> Vec<Vec<(usize, usize)>> {
-        return self.get_numbers().into_iter()
-            .filter(|n| self.is_adjacent_to_any(coord, &n))
+    /// Positions in `index.numbers` of the numbers next to `coord`, in reading order.
+    fn numbers_around(&self, coord: &Coord) -> Vec<usize> {
+        let mut around: Vec<usize> = self.grid.neighbours8(*coord)
+            .filter_map(|c| *self.index.number_at.get(c).unwrap())
             .collect();
-    }
-
-    fn is_adjacent_to_any(&self, coord: &(usize, usize), coords: &Vec<(usize, usize)>) -> bool {
-        return self.get_adjacent_coords(&coord).iter()
-            .any(|adj_c| coords.iter().any(|n| adj_c == n));
+        around.sort();
+        around.dedup();
+        return around;
     }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use std::{io::BufReader, fs::File};
-
-    use crate::day03::{what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic, what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic};
+    use crate::day03::{parse, parse_with_rules, part1, part2, PartNumber, SymbolRules, what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic, what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic};
+    use crate::input::open;
 
     #[test]
     fn sample_part1_input() {
-        let mut f = BufReader::new(File::open("./src/day03/sample.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut f), 4361);
+        let mut f = open(3, "sample").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut f).unwrap(), 4361);
     }
 
     #[test]
     fn part1_input() {
-        let mut f = BufReader::new(File::open("./src/day03/my.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut f), 514969);
+        let mut f = open(3, "my").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut f).unwrap(), 514969);
     }
 
     #[test]
     fn sample_part2_input() {
-        let mut f = BufReader::new(File::open("./src/day03/sample.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(&mut f), 467835);
+        let mut f = open(3, "sample").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(&mut f).unwrap(), 467835);
     }
 
     #[test]
     fn part2_input() {
-        let mut f = BufReader::new(File::open("./src/day03/my.input").unwrap());
-        assert_eq!(what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(&mut f), 78915902);
+        let mut f = open(3, "my").unwrap();
+        assert_eq!(what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(&mut f).unwrap(), 78915902);
+    }
+
+    #[test]
+    fn trailing_blank_line() {
+        let mut input = "467..114..\n...*......\n..35..633.\n\n".as_bytes();
+        assert_eq!(what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut input).unwrap(), 467 + 35);
+    }
+
+    #[test]
+    fn gear_ratio_wider_than_u32() {
+        let mut input = "123456*654321".as_bytes();
+        assert_eq!(what_is_the_sum_of_all_of_the_gear_ratios_in_the_engine_schematic(&mut input).unwrap(), 80_779_853_376);
+    }
+
+    #[test]
+    fn number_overflowing_u64() {
+        let mut input = "123456789012345678901*".as_bytes();
+        let error = what_is_the_sum_of_all_of_the_part_numbers_in_the_engine_schematic(&mut input).unwrap_err();
+        assert_eq!(error.to_string(), "invalid value: number at line 1, column 1 overflows u64");
+    }
+
+    #[test]
+    fn large_schematic() {
+        let rows = format!("{}\n{}\n", "12*34.".repeat(200), "......".repeat(200));
+        let input = rows.repeat(300);
+        let schematic = parse(&mut input.as_bytes()).unwrap();
+
+        assert_eq!(part1(&schematic).unwrap(), 300 * 200 * (12 + 34));
+        assert_eq!(part2(&schematic).unwrap(), 300 * 200 * 12 * 34);
+    }
+
+    #[test]
+    fn part_number_spans() {
+        let schematic = parse(&mut "467..114..\n...*......\n..35..633.\n".as_bytes()).unwrap();
+        let numbers = schematic.get_numbers();
+
+        assert_eq!(numbers[0], PartNumber { row: 0, col_start: 0, col_end: 3, value: 467 });
+        assert_eq!(numbers[2].to_string(), "35 at row 2, columns 2..4");
+        assert_eq!(numbers[2].bounding_box(), ((1, 1), (4, 3)));
+        assert_eq!(numbers[0].neighbours(10, 3).collect::<Vec<_>>(), vec![(3, 0), (0, 1), (1, 1), (2, 1), (3, 1)]);
+        assert_eq!(numbers[2].neighbours(4, 3).collect::<Vec<_>>(), vec![(1, 1), (2, 1), (3, 1), (1, 2)]);
+        assert_eq!(schematic.get_adjacent_numbers(&(3, 1)), vec![numbers[0], numbers[2]]);
+        assert!(!schematic.is_part_number(&numbers[1]));
+    }
+
+    #[test]
+    fn custom_symbol_rules() {
+        let input = "2 3 4\n.#*@.\n5 6 7\n";
+        assert_eq!(part1(&parse(&mut input.as_bytes()).unwrap()).unwrap(), 2 + 3 + 4 + 5 + 6 + 7);
+
+        let rules = SymbolRules::default()
+            .with_empty(&['.', ' '])
+            .with_symbols(&['*', '#'])
+            .with_gears(&[('*', 3), ('#', 4), ('@', 1)]);
+        let schematic = parse_with_rules(&mut input.as_bytes(), rules).unwrap();
+
+        assert!(schematic.rules().is_symbol('#') && !schematic.rules().is_symbol('@'));
+        assert_eq!(part1(&schematic).unwrap(), 2 + 3 + 5 + 6);
+        assert_eq!(schematic.gears().iter().map(|(c, _)| *c).collect::<Vec<_>>(), vec![(1, 1)]);
+        assert_eq!(part2(&schematic).unwrap(), 2 * 3 * 5 * 6);
     }
 }
@@ -0,0 +1,127 @@
+use crate::grid::Coord;
+
+use super::{PartNumber, Schematic};
+
+/// A symbol of the schematic and the numbers touching it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolReport {
+    pub coord: Coord,
+    pub symbol: char,
+    pub numbers: Vec<PartNumber>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// Every symbol in reading order, including those no number touches.
+    pub symbols: Vec<SymbolReport>,
+    pub non_part_numbers: Vec<PartNumber>
+}
+
+impl Report {
+    pub fn lonely_symbols(&self) -> impl Iterator<Item = &SymbolReport> {
+        return self.symbols.iter().filter(|s| s.numbers.is_empty());
+    }
+
+    pub fn to_json(&self) -> String {
+        let symbol = |s: &SymbolReport| format!("{{\"row\": {}, \"column\": {}, \"symbol\": {}", s.coord.1, s.coord.0, json_string(s.symbol));
+        let symbols: Vec<String> = self.symbols.iter()
+            .map(|s| {
+                let numbers: Vec<String> = s.numbers.iter().map(number_json).collect();
+                return format!("    {}, \"numbers\": [{}]}}", symbol(s), numbers.join(", "));
+            })
+            .collect();
+        let non_part_numbers: Vec<String> = self.non_part_numbers.iter().map(|n| format!("    {}", number_json(n))).collect();
+        let lonely_symbols: Vec<String> = self.lonely_symbols().map(|s| format!("    {}}}", symbol(s))).collect();
+
+        return format!("{{\n  \"symbols\": {},\n  \"non_part_numbers\": {},\n  \"lonely_symbols\": {}\n}}\n",
+            json_array(&symbols), json_array(&non_part_numbers), json_array(&lonely_symbols));
+    }
+
+    /// One row per symbol, listing the values of the numbers around it, then one per
+    /// number that is not a part number. Symbols no number touches have the kind
+    /// `lonely-symbol` instead of `symbol`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "kind,row,column,symbol,values\n".to_string();
+        for s in self.symbols.iter() {
+            let values: Vec<String> = s.numbers.iter().map(|n| n.value.to_string()).collect();
+            let kind = if s.numbers.is_empty() { "lonely-symbol" } else { "symbol" };
+            csv.push_str(&format!("{},{},{},{},{}\n", kind, s.coord.1, s.coord.0, csv_field(&s.symbol.to_string()), values.join(" ")));
+        }
+        for n in self.non_part_numbers.iter() {
+            csv.push_str(&format!("non-part-number,{},{},,{}\n", n.row, n.col_start, n.value));
+        }
+        return csv;
+    }
+}
+
+/// Lists every symbol with its adjacent numbers and every number no symbol touches.
+pub fn report(schematic: &Schematic) -> Report {
+    let symbols = schematic.grid.coords()
+        .filter(|c| schematic.is_part(c))
+        .map(|c| SymbolReport {
+            coord: c,
+            symbol: *schematic.get(&c),
+            numbers: schematic.get_adjacent_numbers(&c)
+        })
+        .collect();
+    let non_part_numbers = schematic.get_numbers().iter()
+        .filter(|n| !schematic.is_part_number(n))
+        .copied()
+        .collect();
+
+    return Report {
+        symbols,
+        non_part_numbers
+    };
+}
+
+fn number_json(n: &PartNumber) -> String {
+    return format!("{{\"row\": {}, \"col_start\": {}, \"col_end\": {}, \"value\": {}}}", n.row, n.col_start, n.col_end, n.value);
+}
+
+fn json_array(entries: &[String]) -> String {
+    if entries.is_empty() {
+        return "[]".to_string();
+    }
+    return format!("[\n{}\n  ]", entries.join(",\n"));
+}
+
+fn json_string(c: char) -> String {
+    return match c {
+        '"' | '\\' => format!("\"\\{}\"", c),
+        c if c.is_control() => format!("\"\\u{:04x}\"", c as u32),
+        c => format!("\"{}\"", c)
+    };
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+    return field.to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day03::{parse, report};
+    use crate::input::open;
+
+    #[test]
+    fn sample_report() {
+        let report = report(&parse(&mut open(3, "sample").unwrap()).unwrap());
+
+        assert_eq!(report.symbols.len(), 6);
+        assert_eq!((report.symbols[0].coord, report.symbols[0].symbol), ((3, 1), '*'));
+        assert_eq!(report.symbols[0].numbers.iter().map(|n| n.value).collect::<Vec<_>>(), vec![467, 35]);
+        assert_eq!(report.non_part_numbers.iter().map(|n| n.value).collect::<Vec<_>>(), vec![114, 58]);
+        assert_eq!(report.lonely_symbols().count(), 0);
+    }
+
+    #[test]
+    fn json_and_csv() {
+        let report = report(&parse(&mut "1.\"\n..,\n.7.\n".as_bytes()).unwrap());
+
+        assert_eq!(report.to_json(), "{\n  \"symbols\": [\n    {\"row\": 0, \"column\": 2, \"symbol\": \"\\\"\", \"numbers\": []},\n    {\"row\": 1, \"column\": 2, \"symbol\": \",\", \"numbers\": [{\"row\": 2, \"col_start\": 1, \"col_end\": 2, \"value\": 7}]}\n  ],\n  \"non_part_numbers\": [\n    {\"row\": 0, \"col_start\": 0, \"col_end\": 1, \"value\": 1}\n  ],\n  \"lonely_symbols\": [\n    {\"row\": 0, \"column\": 2, \"symbol\": \"\\\"\"}\n  ]\n}\n");
+        assert_eq!(report.to_csv(), "kind,row,column,symbol,values\nlonely-symbol,0,2,\"\"\"\",\nsymbol,1,2,\",\",7\nnon-part-number,0,0,,1\n");
+    }
+}
@@ -1,72 +1,117 @@
 use std::{io::BufRead, collections::HashMap};
 use regex::Regex;
 
-use crate::read_input;
+use crate::{checked_sum, downcast, read_input, Answer, AocError, Model, Solution, error::parse_number};
 
-pub fn how_many_points_are_cards_worth_in_total(input: &mut dyn BufRead) -> u32 {
-    return read_input(input).iter()
-        .map(|line| parse_card(line))
-        .map(|card| card.how_many_points_is_worth())
-        .sum();
+pub struct Day04;
+
+impl Solution for Day04 {
+    fn day(&self) -> u8 {
+        return 4;
+    }
+
+    fn title(&self) -> &'static str {
+        return "Scratchcards";
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError> {
+        return Ok(Box::new(parse(input)?));
+    }
+
+    fn part1(&self, model: &Model) -> Result<Answer, AocError> {
+        return part1(downcast::<Vec<Card>>(model)?).map(Answer::from);
+    }
+
+    fn part2(&self, model: &Model) -> Result<Answer, AocError> {
+        return part2(downcast::<Vec<Card>>(model)?).map(Answer::from);
+    }
 }
 
-pub fn how_many_total_scratchcards_do_you_end_up_with(input: &mut dyn BufRead) -> u32 {
-    let cards: Vec<Card> = read_input(input).iter()
-        .map(|line| parse_card(line))
+pub fn parse(input: &mut dyn BufRead) -> Result<Vec<Card>, AocError> {
+    return read_input(input)?.iter()
+        .enumerate()
+        .map(|(i, line)| parse_card(i + 1, line))
         .collect();
+}
 
-    let mut scratchcards: HashMap<u32, u32> = HashMap::new();
+pub fn part1(cards: &[Card]) -> Result<u64, AocError> {
+    let points = cards.iter()
+        .map(|card| card.how_many_points_is_worth())
+        .collect::<Result<Vec<u64>, AocError>>()?;
+    return checked_sum(points, "sum of points");
+}
 
-    cards.iter().rev().for_each(|card| {
-        let copies: u32 = (1..=card.how_many_numbers_match())
-            .map(|i| scratchcards.get(&(i + card.number)).unwrap())
-            .sum();
+pub fn part2(cards: &[Card]) -> Result<u64, AocError> {
+    let mut scratchcards: HashMap<u32, u64> = HashMap::new();
 
-        scratchcards.insert(card.number, copies + 1);
-    });
+    for card in cards.iter().rev() {
+        let won = (1..=card.how_many_numbers_match())
+            .map(|i| card.number.checked_add(i)
+                .and_then(|won| scratchcards.get(&won).copied())
+                .ok_or_else(|| AocError::InvalidValue(
+                    format!("card {} wins a copy of card {} which does not exist", card.number, u64::from(card.number) + u64::from(i)))))
+            .collect::<Result<Vec<u64>, AocError>>()?;
+        let copies = checked_sum(won, "number of scratchcards")?;
+
+        scratchcards.insert(card.number, checked_sum([copies, 1], "number of scratchcards")?);
+    }
+
+    return checked_sum(scratchcards.into_values(), "number of scratchcards");
+}
 
-    return scratchcards.values().sum();
+pub fn how_many_points_are_cards_worth_in_total(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part1(&parse(input)?);
 }
 
-fn parse_card(line: &String) -> Card {
+pub fn how_many_total_scratchcards_do_you_end_up_with(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part2(&parse(input)?);
+}
+
+fn parse_card(line_number: usize, line: &str) -> Result<Card, AocError> {
+    if !line.contains('|') {
+        return Err(AocError::parse(line_number, line.len() + 1, "expected '|'"));
+    }
+
     let re = Regex::new(r"Card\s+(\d+):\s+(.*)\s+\|\s+(.*)").unwrap();
-    let captures = re.captures(line).unwrap();
-    let number = captures.get(1).unwrap().as_str().parse::<u32>().unwrap();
+    let captures = re.captures(line)
+        .ok_or_else(|| AocError::parse(line_number, 1, "expected 'Card <id>: <numbers> | <numbers>'"))?;
+    let number = parse_number(line_number, line, captures.get(1).unwrap().as_str())?;
     let winning_numbers = captures.get(2).unwrap().as_str()
         .split_whitespace()
-        .map(|n| n.parse::<u32>().unwrap())
-        .collect();
+        .map(|n| parse_number(line_number, line, n))
+        .collect::<Result<Vec<u32>, AocError>>()?;
     let your_numbers = captures.get(3).unwrap().as_str()
         .split_whitespace()
-        .map(|n| n.parse::<u32>().unwrap())
-        .collect();
+        .map(|n| parse_number(line_number, line, n))
+        .collect::<Result<Vec<u32>, AocError>>()?;
 
-    return Card {
+    return Ok(Card {
         number,
         winning_numbers,
         your_numbers
-    };
+    });
 }
 
-struct Card {
-    number: u32,
-    winning_numbers: Vec<u32>,
-    your_numbers: Vec<u32>
+pub struct Card {
+    pub number: u32,
+    pub winning_numbers: Vec<u32>,
+    pub your_numbers: Vec<u32>
 }
 
 impl Card {
-    fn how_many_points_is_worth(&self) -> u32 {
+    pub fn how_many_points_is_worth(&self) -> Result<u64, AocError> {
         let n = self.how_many_numbers_match();
     
         if n > 0 {
-            return (2 as u32).pow(n - 1);
+            return 2_u64.checked_pow(n - 1)
+                .ok_or_else(|| AocError::InvalidValue(format!("points of card {} overflow u64", self.number)));
         }
         else {
-            return 0;
+            return Ok(0);
         }
     }
 
-    fn how_many_numbers_match(&self) -> u32 {
+    pub fn how_many_numbers_match(&self) -> u32 {
         return self.your_numbers.iter()
             .filter(|&n| self.winning_numbers.contains(n))
             .collect::<Vec<&u32>>()
@@ -76,31 +121,48 @@ impl Card {
 
 #[cfg(test)]
 mod tests {
-    use std::{io::BufReader, fs::File};
-
     use crate::day04::{how_many_points_are_cards_worth_in_total, how_many_total_scratchcards_do_you_end_up_with};
+    use crate::input::open;
 
     #[test]
     fn sample_part1_input() {
-        let mut f = BufReader::new(File::open("./src/day04/sample.input").unwrap());
-        assert_eq!(how_many_points_are_cards_worth_in_total(&mut f), 13);
+        let mut f = open(4, "sample").unwrap();
+        assert_eq!(how_many_points_are_cards_worth_in_total(&mut f).unwrap(), 13);
     }
 
     #[test]
     fn part1_input() {
-        let mut f = BufReader::new(File::open("./src/day04/my.input").unwrap());
-        assert_eq!(how_many_points_are_cards_worth_in_total(&mut f), 32001);
+        let mut f = open(4, "my").unwrap();
+        assert_eq!(how_many_points_are_cards_worth_in_total(&mut f).unwrap(), 32001);
     }
 
     #[test]
     fn sample_part2_input() {
-        let mut f = BufReader::new(File::open("./src/day04/sample.input").unwrap());
-        assert_eq!(how_many_total_scratchcards_do_you_end_up_with(&mut f), 30);
+        let mut f = open(4, "sample").unwrap();
+        assert_eq!(how_many_total_scratchcards_do_you_end_up_with(&mut f).unwrap(), 30);
     }
 
     #[test]
     fn part2_input() {
-        let mut f = BufReader::new(File::open("./src/day04/my.input").unwrap());
-        assert_eq!(how_many_total_scratchcards_do_you_end_up_with(&mut f), 5037841);
+        let mut f = open(4, "my").unwrap();
+        assert_eq!(how_many_total_scratchcards_do_you_end_up_with(&mut f).unwrap(), 5037841);
+    }
+
+    #[test]
+    fn missing_separator() {
+        let error = how_many_points_are_cards_worth_in_total(&mut "Card 1: 41 48 83 | 83 86\nCard 2: 13 32 20 61".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 20: expected '|'");
+    }
+
+    #[test]
+    fn invalid_number() {
+        let error = how_many_points_are_cards_worth_in_total(&mut "Card 1: 41 4x | 83 86".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 1, column 12: invalid number '4x'");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn winning_past_the_last_card_number() {
+        let error = how_many_total_scratchcards_do_you_end_up_with(&mut "Card 4294967295: 1 | 1".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "invalid value: card 4294967295 wins a copy of card 4294967296 which does not exist");
+    }
+}
@@ -0,0 +1,444 @@
+use std::{collections::{BTreeMap, HashMap, HashSet, VecDeque}, fmt, ops::Range};
+
+use crate::AocError;
+
+use super::map::{merge_ranges, shift, Line, Map};
+
+pub const MAX_HISTOGRAM_BUCKETS: u64 = 100_000;
+
+/// One map applied to one value, as recorded by [`Almanac::trace`].
+pub struct Step<'a> {
+    pub map: &'a Map,
+    pub input: u64,
+    /// The line that moved `input`, or `None` where the value maps to itself.
+    pub line: Option<&'a Line>,
+    pub output: u64
+}
+
+pub struct Almanac {
+    pub seeds: Vec<u64>,
+    pub maps: Vec<Map>
+}
+
+impl Almanac {
+    /// The seed numbers read as `start length` pairs, as `[start, start + length)` intervals.
+    pub fn seed_ranges(&self) -> Result<Vec<Range<u64>>, AocError> {
+        if !self.seeds.len().is_multiple_of(2) {
+            return Err(AocError::InvalidValue("expected pairs of seed range start and length".to_string()));
+        }
+
+        let ranges = self.seeds
+            .chunks(2)
+            .map(|chunk| chunk[0].checked_add(chunk[1])
+                .map(|end| chunk[0]..end)
+                .ok_or_else(|| AocError::InvalidValue(format!("seed range {} {} overflows u64", chunk[0], chunk[1]))))
+            .collect::<Result<Vec<Range<u64>>, AocError>>()?;
+        return Ok(merge_ranges(ranges));
+    }
+
+    pub fn get_location(&self, seed: u64) -> Result<u64, AocError> {
+        return self.map_value("seed", "location", seed);
+    }
+
+    pub fn map_value(&self, from: &str, to: &str, value: u64) -> Result<u64, AocError> {
+        return Ok(self.path(from, to)?.iter()
+            .fold(value, |value, map| map.map(value)));
+    }
+
+    /// The parts of the seed ranges whose locations fall in `locations`.
+    pub fn seeds_in_locations(&self, locations: Range<u64>) -> Result<Vec<Range<u64>>, AocError> {
+        let pieces = self.split_ranges("seed", "location", self.seed_ranges()?)?;
+        return Ok(merge_ranges(pieces.iter()
+            .filter_map(|(seeds, offset)| {
+                let image = shift(seeds, *offset);
+                let hit = image.start.max(locations.start)..image.end.min(locations.end);
+                return (!hit.is_empty()).then(|| shift(&hit, -offset));
+            })
+            .collect()));
+    }
+
+    /// How many seeds of the seed ranges land in each `[k * bucket_width, (k + 1) * bucket_width)`
+    /// location bucket, leaving out empty buckets. Widths that would give more than
+    /// [`MAX_HISTOGRAM_BUCKETS`] buckets are rejected.
+    pub fn location_histogram(&self, bucket_width: u64) -> Result<Vec<(Range<u64>, u64)>, AocError> {
+        if bucket_width == 0 {
+            return Err(AocError::InvalidValue("bucket width must be positive".to_string()));
+        }
+
+        // Seeds reaching the same location each count, so the pieces are not merged.
+        let locations: Vec<Range<u64>> = self.split_ranges("seed", "location", self.seed_ranges()?)?.iter()
+            .map(|(seeds, offset)| shift(seeds, *offset))
+            .collect();
+        let buckets: u64 = merge_ranges(locations.clone()).iter()
+            .map(|r| (r.end - 1) / bucket_width - r.start / bucket_width + 1)
+            .fold(0, u64::saturating_add);
+        if buckets > MAX_HISTOGRAM_BUCKETS {
+            return Err(AocError::InvalidValue(format!("bucket width {} gives {} buckets, at most {} are allowed", bucket_width, buckets, MAX_HISTOGRAM_BUCKETS)));
+        }
+
+        let mut counts: BTreeMap<u64, u64> = BTreeMap::new();
+        for range in locations {
+            for bucket in range.start / bucket_width..=(range.end - 1) / bucket_width {
+                let start = (bucket * bucket_width).max(range.start);
+                let end = bucket.saturating_add(1).saturating_mul(bucket_width).min(range.end);
+                *counts.entry(bucket).or_insert(0) += end - start;
+            }
+        }
+
+        return Ok(counts.into_iter()
+            .map(|(bucket, count)| (bucket * bucket_width..bucket.saturating_add(1).saturating_mul(bucket_width), count))
+            .collect());
+    }
+
+    /// Splits `ranges` of category `from` into pieces that each move to `to` by a single offset.
+    fn split_ranges(&self, from: &str, to: &str, ranges: Vec<Range<u64>>) -> Result<Vec<(Range<u64>, i64)>, AocError> {
+        let mut pieces: Vec<(Range<u64>, i64)> = merge_ranges(ranges).into_iter().map(|r| (r, 0)).collect();
+        for map in self.path(from, to)? {
+            pieces = pieces.into_iter()
+                .flat_map(|(range, offset)| map.split_ranges(&[shift(&range, offset)]).into_iter()
+                    .map(move |(piece, next_offset)| (shift(&piece, -offset), offset + next_offset)))
+                .collect();
+        }
+        return Ok(pieces);
+    }
+
+    /// Like [`Almanac::map_value`], but keeps every step taken along the way.
+    pub fn trace(&self, from: &str, to: &str, value: u64) -> Result<Vec<Step<'_>>, AocError> {
+        let mut steps = Vec::new();
+        let mut value = value;
+        for map in self.path(from, to)? {
+            let line = map.matching_line(value);
+            let output = map.map(value);
+            steps.push(Step { map, input: value, line, output });
+            value = output;
+        }
+        return Ok(steps);
+    }
+
+    pub fn trace_location(&self, seed: u64) -> Result<Vec<Step<'_>>, AocError> {
+        return self.trace("seed", "location", seed);
+    }
+
+    pub fn map_ranges(&self, from: &str, to: &str, ranges: Vec<Range<u64>>) -> Result<Vec<Range<u64>>, AocError> {
+        return Ok(self.path(from, to)?.iter()
+            .fold(merge_ranges(ranges), |ranges, map| map.map_ranges(&ranges)));
+    }
+
+    /// An equivalent almanac with every map normalized and the gaps between its lines
+    /// written out as identity lines.
+    pub fn normalized(&self) -> Almanac {
+        return Almanac {
+            seeds: self.seeds.clone(),
+            maps: self.maps.iter().map(|map| map.normalized().with_identity_gaps()).collect()
+        };
+    }
+
+    /// The maps from `from` to `to` composed into one normalized map.
+    pub fn collapse(&self, from: &str, to: &str) -> Result<Map, AocError> {
+        let identity = Map {
+            source: from.to_string(),
+            destination: from.to_string(),
+            lines: Vec::new()
+        };
+        return Ok(self.path(from, to)?.iter()
+            .fold(identity, |collapsed, map| collapsed.compose(map)));
+    }
+
+    /// Every seed that ends up as `value` in `category`, in ascending order.
+    pub fn reverse_lookup(&self, category: &str, value: u64) -> Result<Vec<u64>, AocError> {
+        return Ok(reverse_along(&self.path("seed", category)?, value));
+    }
+
+    /// Finds the lowest location of any seed range by checking locations from 0 upwards
+    /// until one leads back to a seed inside a seed range.
+    pub fn lowest_location_by_reverse_search(&self) -> Result<u64, AocError> {
+        let seed_ranges = self.seed_ranges()?;
+        let path = self.path("seed", "location")?;
+        let highest = self.map_ranges("seed", "location", seed_ranges.clone())?
+            .last()
+            .map(|range| range.end)
+            .ok_or_else(|| AocError::InvalidValue("no seeds".to_string()))?;
+
+        return (0..highest)
+            .find(|&location| reverse_along(&path, location).iter()
+                .any(|seed| seed_ranges.iter().any(|range| range.contains(seed))))
+            .ok_or_else(|| AocError::InvalidValue("no location leads back to a seed range".to_string()));
+    }
+
+    /// The shortest chain of maps leading from category `from` to category `to`.
+    pub fn path(&self, from: &str, to: &str) -> Result<Vec<&Map>, AocError> {
+        let start = self.maps_from(from).first().map(|map| map.source.as_str());
+        if let Some(cycle) = start.and_then(|start| self.find_cycle(start, &mut Vec::new(), &mut HashSet::new())) {
+            return Err(AocError::InvalidValue(format!("cyclic maps: {}", cycle.join(" -> "))));
+        }
+
+        if from == to {
+            return Ok(Vec::new());
+        }
+
+        let mut reached_by: HashMap<&str, &Map> = HashMap::new();
+        let mut queue: VecDeque<&Map> = VecDeque::new();
+        for map in self.maps_from(from) {
+            reached_by.entry(&map.destination).or_insert(map);
+            queue.push_back(map);
+        }
+
+        while let Some(map) = queue.pop_front() {
+            if map.destination == to {
+                let mut path = vec![map];
+                while path.last().unwrap().source != from {
+                    path.push(reached_by[path.last().unwrap().source.as_str()]);
+                }
+                path.reverse();
+                return Ok(path);
+            }
+
+            for next in self.maps_from(&map.destination) {
+                if next.destination != from && !reached_by.contains_key(next.destination.as_str()) {
+                    reached_by.insert(&next.destination, next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        return Err(AocError::MissingSection(format!("maps from '{}' to '{}'", from, to)));
+    }
+
+    fn maps_from(&self, category: &str) -> Vec<&Map> {
+        return self.maps.iter().filter(|map| map.source == category).collect();
+    }
+
+    fn find_cycle<'a>(&'a self, category: &'a str, stack: &mut Vec<&'a str>, done: &mut HashSet<&'a str>) -> Option<Vec<&'a str>> {
+        if let Some(i) = stack.iter().position(|c| *c == category) {
+            let mut cycle = stack[i..].to_vec();
+            cycle.push(category);
+            return Some(cycle);
+        }
+        if done.contains(category) {
+            return None;
+        }
+
+        stack.push(category);
+        for map in self.maps_from(category) {
+            if let Some(cycle) = self.find_cycle(&map.destination, stack, done) {
+                return Some(cycle);
+            }
+        }
+        stack.pop();
+        done.insert(category);
+
+        return None;
+    }
+}
+
+/// Formats the almanac in the puzzle input format, so parsing the result gives it back.
+impl fmt::Display for Almanac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seeds: Vec<String> = self.seeds.iter().map(|s| s.to_string()).collect();
+        writeln!(f, "seeds: {}", seeds.join(" "))?;
+        for map in self.maps.iter() {
+            write!(f, "\n{}", map)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Walks `path` backwards from a value in its last category to all values in its first.
+fn reverse_along(path: &[&Map], value: u64) -> Vec<u64> {
+    let mut values = vec![value];
+    for map in path.iter().rev() {
+        values = values.iter().flat_map(|&v| map.preimages(v)).collect();
+        values.sort();
+        values.dedup();
+    }
+    return values;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::day05::{generate, parse, part1, part2, Almanac, GeneratorConfig, Map};
+    use crate::input::{open, InputResolver};
+
+    fn almanac(links: &[(&str, &str)]) -> Almanac {
+        return Almanac {
+            seeds: vec![],
+            maps: links.iter()
+                .map(|(source, destination)| Map {
+                    source: source.to_string(),
+                    destination: destination.to_string(),
+                    lines: vec![]
+                })
+                .collect()
+        };
+    }
+
+    #[test]
+    fn map_between_any_categories() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        assert_eq!(almanac.get_location(79).unwrap(), 82);
+        assert_eq!(almanac.map_value("humidity", "location", 78).unwrap(), 82);
+        assert_eq!(almanac.map_value("soil", "light", 81).unwrap(), 74);
+        assert_eq!(almanac.map_value("water", "water", 81).unwrap(), 81);
+    }
+
+    #[test]
+    fn seed_range_overflowing_u64() {
+        let almanac = parse(&mut "seeds: 18446744073709551615 2\n\nseed-to-location map:\n0 1 1\n".as_bytes()).unwrap();
+        assert_eq!(almanac.seed_ranges().unwrap_err().to_string(), "invalid value: seed range 18446744073709551615 2 overflows u64");
+        assert!(part2(&almanac).is_err());
+    }
+
+    #[test]
+    fn seeds_landing_in_locations() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        assert_eq!(almanac.seeds_in_locations(46..47).unwrap(), vec![82..83]);
+        assert_eq!(almanac.seeds_in_locations(0..46).unwrap(), vec![]);
+
+        for seed in 0..100 {
+            let almanac = generate(&GeneratorConfig { max_value: 200, max_seed_range_width: 40, ..GeneratorConfig::default() }, seed);
+            let seeds: Vec<u64> = almanac.seed_ranges().unwrap().into_iter().flatten().collect();
+            let expected: Vec<u64> = seeds.iter().copied().filter(|&s| (50..120).contains(&almanac.get_location(s).unwrap())).collect();
+            let found: Vec<u64> = almanac.seeds_in_locations(50..120).unwrap().into_iter().flatten().collect();
+            assert_eq!(found, expected, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn histogram_of_locations() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        let histogram = almanac.location_histogram(25).unwrap();
+
+        assert_eq!(histogram.iter().map(|(_, count)| count).sum::<u64>(), 14 + 13);
+        assert_eq!(histogram[0].0, 25..50);
+        for (bucket, count) in histogram {
+            assert_eq!(count, almanac.seeds_in_locations(bucket).unwrap().iter().map(|r| r.end - r.start).sum::<u64>());
+        }
+        assert!(almanac.location_histogram(0).is_err());
+        assert_eq!(almanac.location_histogram(1).unwrap().len(), 27);
+
+        let almanac = parse(&mut open(5, "my").unwrap()).unwrap();
+        assert!(almanac.location_histogram(1).unwrap_err().to_string().ends_with("at most 100000 are allowed"));
+        assert_eq!(almanac.location_histogram(1 << 32).unwrap().iter().map(|(_, count)| count).sum::<u64>(),
+            almanac.seed_ranges().unwrap().iter().map(|r| r.end - r.start).sum::<u64>());
+    }
+
+    #[test]
+    fn histogram_counts_seeds_sharing_a_location() {
+        let almanac = parse(&mut "seeds: 0 10\n\nseed-to-location map:\n0 1 1\n".as_bytes()).unwrap();
+        assert_eq!(almanac.location_histogram(10).unwrap(), vec![(0..10, 10)]);
+        assert_eq!(almanac.location_histogram(1).unwrap()[0], (0..1, 2));
+
+        for seed in 0..50 {
+            let config = GeneratorConfig { max_value: 200, max_seed_range_width: 40, overlapping_lines: 3, ..GeneratorConfig::default() };
+            let almanac = generate(&config, seed);
+            let seeds = almanac.seed_ranges().unwrap().iter().map(|r| r.end - r.start).sum::<u64>();
+            let histogram = almanac.location_histogram(7).unwrap();
+
+            assert_eq!(histogram.iter().map(|(_, count)| count).sum::<u64>(), seeds, "seed {}", seed);
+            for (bucket, count) in histogram {
+                assert_eq!(count, almanac.seeds_in_locations(bucket).unwrap().iter().map(|r| r.end - r.start).sum::<u64>(), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn trace_each_step() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        let steps: Vec<(&str, u64, Option<String>, u64)> = almanac.trace_location(79).unwrap().iter()
+            .map(|s| (s.map.source.as_str(), s.input, s.line.map(|l| l.to_string()), s.output))
+            .collect();
+
+        assert_eq!(steps, vec![
+            ("seed", 79, Some("52 50 48".to_string()), 81),
+            ("soil", 81, None, 81),
+            ("fertilizer", 81, None, 81),
+            ("water", 81, Some("18 25 70".to_string()), 74),
+            ("light", 74, Some("68 64 13".to_string()), 78),
+            ("temperature", 78, None, 78),
+            ("humidity", 78, Some("60 56 37".to_string()), 82)
+        ]);
+        assert_eq!(almanac.trace("soil", "soil", 5).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn collapse_into_one_map() {
+        let almanac = parse(&mut open(5, "my").unwrap()).unwrap();
+        let collapsed = almanac.collapse("seed", "location").unwrap();
+
+        assert_eq!((collapsed.source.as_str(), collapsed.destination.as_str()), ("seed", "location"));
+        for range in almanac.seed_ranges().unwrap() {
+            for seed in [range.start, range.start + 1, (range.start + range.end) / 2, range.end - 1, range.end] {
+                assert_eq!(collapsed.lookup(seed), almanac.get_location(seed).unwrap());
+            }
+        }
+        assert_eq!(almanac.collapse("seed", "seed").unwrap().lines.len(), 0);
+    }
+
+    #[test]
+    fn write_back_to_input_format() {
+        let text = fs::read_to_string(InputResolver::from_env().repository_input(5, "sample")).unwrap();
+        let almanac = parse(&mut text.as_bytes()).unwrap();
+        assert_eq!(almanac.to_string(), format!("{}\n", text.trim_end()));
+    }
+
+    #[test]
+    fn normalized_round_trip() {
+        let almanac = parse(&mut open(5, "my").unwrap()).unwrap();
+        let normalized = almanac.normalized();
+        let reparsed = parse(&mut normalized.to_string().as_bytes()).unwrap();
+
+        assert_eq!(reparsed.to_string(), normalized.to_string());
+        assert_eq!(part1(&reparsed).unwrap(), 173706076);
+        assert_eq!(part2(&reparsed).unwrap(), 11611182);
+        for map in reparsed.maps.iter() {
+            assert!(map.lines.windows(2).all(|w| w[0].source_range_end == w[1].source_range_start));
+        }
+    }
+
+    #[test]
+    fn reverse_lookup_to_seeds() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        assert_eq!(almanac.reverse_lookup("location", 82).unwrap(), vec![79]);
+        assert_eq!(almanac.reverse_lookup("location", 35).unwrap(), vec![13]);
+        assert_eq!(almanac.reverse_lookup("soil", 81).unwrap(), vec![79]);
+        assert_eq!(almanac.reverse_lookup("seed", 7).unwrap(), vec![7]);
+        assert_eq!(almanac.reverse_lookup("location", 1 << 63).unwrap(), vec![1 << 63]);
+
+        let almanac = parse(&mut "seeds: 1 1\n\nseed-to-location map:\n0 9000000000000000000 10\n".as_bytes()).unwrap();
+        assert_eq!(almanac.reverse_lookup("location", 9_000_000_000_000_000_000).unwrap(), Vec::<u64>::new());
+        assert_eq!(almanac.reverse_lookup("location", 3).unwrap(), vec![3, 9_000_000_000_000_000_003]);
+
+        for seed in [0, 14, 55, 79, 98, 99] {
+            let location = almanac.get_location(seed).unwrap();
+            assert!(almanac.reverse_lookup("location", location).unwrap().contains(&seed));
+        }
+    }
+
+    #[test]
+    fn reverse_search_finds_lowest_location() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        assert_eq!(almanac.lowest_location_by_reverse_search().unwrap(), 46);
+    }
+
+    #[test]
+    fn path_follows_category_names_not_file_order() {
+        let almanac = almanac(&[("b", "c"), ("a", "b"), ("a", "x"), ("c", "d")]);
+        let path: Vec<&str> = almanac.path("a", "d").unwrap().iter().map(|m| m.destination.as_str()).collect();
+        assert_eq!(path, vec!["b", "c", "d"]);
+    }
+
+    #[test]
+    fn missing_link() {
+        let almanac = almanac(&[("seed", "soil"), ("fertilizer", "water")]);
+        assert_eq!(almanac.path("seed", "water").err().unwrap().to_string(), "missing section: maps from 'seed' to 'water'");
+        assert!(almanac.path("water", "seed").is_err());
+    }
+
+    #[test]
+    fn cyclic_link() {
+        let almanac = almanac(&[("seed", "soil"), ("soil", "water"), ("water", "soil"), ("water", "location")]);
+        assert_eq!(almanac.path("seed", "location").err().unwrap().to_string(), "invalid value: cyclic maps: soil -> water -> soil");
+    }
+}
@@ -0,0 +1,157 @@
+use std::{fmt, io::BufRead};
+
+use crate::AocError;
+
+use super::{read_almanac, Event};
+
+/// Something suspicious about one line of a map section.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    ZeroLength,
+    Overflow,
+    /// The source range shares values with the one on `other_line`, so the result depends on line order.
+    OverlappingSource { other_line: usize },
+    /// The destination range shares values with the one on `other_line`, so the map is not reversible.
+    OverlappingDestination { other_line: usize }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub map: String,
+    pub problem: Problem
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} ({}): ", self.line, self.map)?;
+        return match self.problem {
+            Problem::ZeroLength => write!(f, "range has length 0"),
+            Problem::Overflow => write!(f, "range does not fit in i64"),
+            Problem::OverlappingSource { other_line } => write!(f, "source range overlaps line {}", other_line),
+            Problem::OverlappingDestination { other_line } => write!(f, "destination range overlaps line {}", other_line)
+        };
+    }
+}
+
+/// One parsed map line, before it is turned into a [`super::Line`].
+struct Entry {
+    line: usize,
+    destination: u64,
+    source: u64,
+    length: u64
+}
+
+/// Whether a `<destination> <source> <length>` line has values [`super::Line::map`] cannot
+/// represent, since it shifts values through `i64`.
+pub(super) fn overflows(destination: u64, source: u64, length: u64) -> bool {
+    return [destination, source].iter()
+        .any(|start| start.checked_add(length).is_none_or(|end| end > i64::MAX as u64));
+}
+
+/// Reads an almanac and reports every map line that is empty, overflows, or overlaps another
+/// line of its map. Malformed input is still an error.
+pub fn check(input: &mut dyn BufRead) -> Result<Vec<Issue>, AocError> {
+    let mut issues = Vec::new();
+    let mut map = String::new();
+    let mut entries: Vec<Entry> = Vec::new();
+
+    read_almanac(input, |line, event| {
+        match event {
+            Event::Seeds(_) => {},
+            Event::Header(source, destination) => {
+                issues.extend(check_map(&map, &entries));
+                map = format!("{}-to-{}", source, destination);
+                entries.clear();
+            },
+            Event::Entry(destination, source, length) => entries.push(Entry { line, destination, source, length })
+        }
+        return Ok(());
+    })?;
+    issues.extend(check_map(&map, &entries));
+
+    return Ok(issues);
+}
+
+fn check_map(map: &str, entries: &[Entry]) -> Vec<Issue> {
+    let mut found: Vec<(usize, Problem)> = Vec::new();
+    for entry in entries.iter() {
+        if entry.length == 0 {
+            found.push((entry.line, Problem::ZeroLength));
+        }
+        if overflows(entry.destination, entry.source, entry.length) {
+            found.push((entry.line, Problem::Overflow));
+        }
+    }
+
+    let valid: Vec<&Entry> = entries.iter()
+        .filter(|e| e.length > 0 && !overflows(e.destination, e.source, e.length))
+        .collect();
+    for (line, other_line) in overlaps(&valid, |e| e.source) {
+        found.push((line, Problem::OverlappingSource { other_line }));
+    }
+    for (line, other_line) in overlaps(&valid, |e| e.destination) {
+        found.push((line, Problem::OverlappingDestination { other_line }));
+    }
+
+    found.sort_by_key(|(line, _)| *line);
+    return found.into_iter()
+        .map(|(line, problem)| Issue { line, map: map.to_string(), problem })
+        .collect();
+}
+
+/// Pairs of `(line, earlier line)` whose `[start, start + length)` ranges overlap. Each
+/// range is compared with the one reaching furthest among those starting before it.
+fn overlaps(entries: &[&Entry], start: impl Fn(&Entry) -> u64) -> Vec<(usize, usize)> {
+    let mut sorted: Vec<&Entry> = entries.to_vec();
+    sorted.sort_by_key(|e| (start(e), e.line));
+
+    let mut pairs = Vec::new();
+    let mut furthest: Option<(u64, usize)> = None;
+    for entry in sorted {
+        let end = start(entry) + entry.length;
+        if let Some((furthest_end, furthest_line)) = furthest {
+            if start(entry) < furthest_end {
+                pairs.push((entry.line.max(furthest_line), entry.line.min(furthest_line)));
+            }
+        }
+        if furthest.is_none_or(|(furthest_end, _)| end > furthest_end) {
+            furthest = Some((end, entry.line));
+        }
+    }
+
+    return pairs;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day05::{check, Problem};
+    use crate::input::open;
+
+    #[test]
+    fn inputs_are_clean() {
+        assert_eq!(check(&mut open(5, "sample").unwrap()).unwrap(), vec![]);
+        assert_eq!(check(&mut open(5, "my").unwrap()).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn reports_problems_with_line_numbers() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n50 98 2\n10 99 5\n51 0 3\n7 7 0\n\nsoil-to-water map:\n0 9223372036854775800 10\n";
+        let issues = check(&mut input.as_bytes()).unwrap();
+        let found: Vec<(usize, &str, Problem)> = issues.iter().map(|i| (i.line, i.map.as_str(), i.problem.clone())).collect();
+
+        assert_eq!(found, vec![
+            (5, "seed-to-soil", Problem::OverlappingSource { other_line: 4 }),
+            (6, "seed-to-soil", Problem::OverlappingDestination { other_line: 4 }),
+            (7, "seed-to-soil", Problem::ZeroLength),
+            (10, "soil-to-water", Problem::Overflow)
+        ]);
+        assert_eq!(issues[0].to_string(), "line 5 (seed-to-soil): source range overlaps line 4");
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        let input = "seeds: 1 2\n\nseed-to-soil map:\n50 98\n";
+        assert_eq!(check(&mut input.as_bytes()).unwrap_err().to_string(), "line 4, column 1: seed-to-soil map: expected '<destination> <source> <length>', found 2 numbers");
+    }
+}
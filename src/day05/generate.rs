@@ -0,0 +1,195 @@
+use super::{Almanac, Line, Map};
+
+/// Sizes of a generated almanac.
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub maps: usize,
+    pub lines_per_map: usize,
+    pub seed_ranges: usize,
+    pub max_seed_range_width: u64,
+    /// Lines only move values below this bound, larger ones map to themselves.
+    pub max_value: u64,
+    /// Chance in percent that a piece of a map gets no line, leaving a gap that maps to itself.
+    pub gap_percent: u64,
+    /// Extra lines per map placed over other lines' source ranges, so line order decides
+    /// which one applies.
+    pub overlapping_lines: usize
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> GeneratorConfig {
+        return GeneratorConfig {
+            maps: 7,
+            lines_per_map: 10,
+            seed_ranges: 10,
+            max_seed_range_width: 1_000,
+            max_value: 100_000,
+            gap_percent: 0,
+            overlapping_lines: 0
+        };
+    }
+}
+
+/// A valid almanac from `seed` to `location`, the same for the same `config` and `seed`.
+/// Each map cuts `[0, max_value)` into touching ranges and moves them to a shuffled order,
+/// so source ranges and destination ranges never overlap but often share boundaries.
+/// `gap_percent` and `overlapping_lines` relax this to cover identity gaps and overlaps.
+pub fn generate(config: &GeneratorConfig, seed: u64) -> Almanac {
+    let mut rng = SplitMix64(seed);
+    let categories: Vec<String> = (0..=config.maps)
+        .map(|i| match i {
+            0 => "seed".to_string(),
+            i if i == config.maps => "location".to_string(),
+            i => format!("stage{}", i)
+        })
+        .collect();
+
+    let maps = categories.windows(2)
+        .map(|pair| Map {
+            source: pair[0].clone(),
+            destination: pair[1].clone(),
+            lines: generate_lines(&mut rng, config)
+        })
+        .collect();
+
+    let seeds = (0..config.seed_ranges)
+        .flat_map(|_| [rng.below(config.max_value + config.max_seed_range_width), 1 + rng.below(config.max_seed_range_width)])
+        .collect();
+
+    return Almanac {
+        seeds,
+        maps
+    };
+}
+
+fn generate_lines(rng: &mut SplitMix64, config: &GeneratorConfig) -> Vec<Line> {
+    let max_value = config.max_value;
+    let count = config.lines_per_map.min(max_value as usize);
+    let mut cuts: Vec<u64> = (1..count).map(|_| 1 + rng.below(max_value - 1)).collect();
+    cuts.extend([0, max_value]);
+    cuts.sort();
+    cuts.dedup();
+
+    let pieces: Vec<(u64, u64)> = cuts.windows(2).map(|w| (w[0], w[1] - w[0])).collect();
+    let mut order: Vec<usize> = (0..pieces.len()).collect();
+    rng.shuffle(&mut order);
+
+    let mut destination_start = 0;
+    let mut lines = Vec::with_capacity(pieces.len());
+    for i in order {
+        let (start, length) = pieces[i];
+        lines.push(Line {
+            source_range_start: start,
+            source_range_end: start + length,
+            mapping: destination_start as i64 - start as i64
+        });
+        destination_start += length;
+    }
+    lines.retain(|_| rng.below(100) >= config.gap_percent);
+
+    for _ in 0..config.overlapping_lines {
+        let start = rng.below(max_value);
+        let end = start + 1 + rng.below(max_value - start);
+        let destination_start = rng.below(max_value);
+        lines.push(Line {
+            source_range_start: start,
+            source_range_end: end,
+            mapping: destination_start as i64 - start as i64
+        });
+    }
+
+    rng.shuffle(&mut lines);
+    return lines;
+}
+
+/// A small, fast PRNG so generated inputs do not depend on an external crate.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        return self.next() % bound.max(1);
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day05::{check, generate, parse, part1, part2, GeneratorConfig, Problem};
+
+    fn small(seed: u64) -> GeneratorConfig {
+        return GeneratorConfig {
+            maps: 1 + (seed % 4) as usize,
+            lines_per_map: 1 + (seed % 6) as usize,
+            seed_ranges: 1 + (seed % 3) as usize,
+            max_seed_range_width: 30,
+            max_value: 120,
+            gap_percent: [0, 30, 60][(seed % 3) as usize],
+            overlapping_lines: (seed % 5) as usize
+        };
+    }
+
+    #[test]
+    fn same_seed_same_almanac() {
+        let config = GeneratorConfig::default();
+        assert_eq!(generate(&config, 7).to_string(), generate(&config, 7).to_string());
+        assert_ne!(generate(&config, 7).to_string(), generate(&config, 8).to_string());
+    }
+
+    #[test]
+    fn generated_inputs_are_valid() {
+        let config = GeneratorConfig { gap_percent: 20, ..GeneratorConfig::default() };
+        for seed in 0..20 {
+            let text = generate(&config, seed).to_string();
+            assert_eq!(check(&mut text.as_bytes()).unwrap(), vec![]);
+            assert_eq!(parse(&mut text.as_bytes()).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn gaps_and_overlaps() {
+        let config = GeneratorConfig { gap_percent: 50, overlapping_lines: 3, ..GeneratorConfig::default() };
+        let almanac = generate(&config, 1);
+
+        let unmapped = (0..config.max_value).filter(|&v| almanac.maps[0].matching_line(v).is_none()).count();
+        assert!(unmapped > 0);
+        assert!(almanac.maps.iter().all(|map| map.lines.len() < config.lines_per_map + 3));
+
+        let problems = check(&mut almanac.to_string().as_bytes()).unwrap();
+        assert!(problems.iter().any(|issue| matches!(issue.problem, Problem::OverlappingSource { .. })));
+    }
+
+    #[test]
+    fn range_paths_agree_with_brute_force() {
+        for seed in 0..200 {
+            let almanac = generate(&small(seed), seed);
+            let seed_ranges = almanac.seed_ranges().unwrap();
+            let brute_force = seed_ranges.iter()
+                .flat_map(|range| range.clone())
+                .map(|s| almanac.get_location(s).unwrap())
+                .min()
+                .unwrap();
+
+            assert_eq!(part2(&almanac).unwrap(), brute_force, "seed {}", seed);
+            assert_eq!(almanac.lowest_location_by_reverse_search().unwrap(), brute_force, "seed {}", seed);
+
+            let collapsed = almanac.collapse("seed", "location").unwrap();
+            assert_eq!(part1(&almanac).unwrap(), almanac.seeds.iter().map(|&s| collapsed.lookup(s)).min().unwrap(), "seed {}", seed);
+            for value in 0..160 {
+                assert_eq!(collapsed.lookup(value), almanac.get_location(value).unwrap(), "seed {}, value {}", seed, value);
+            }
+        }
+    }
+}
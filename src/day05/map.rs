@@ -0,0 +1,394 @@
+use std::{fmt, ops::Range};
+
+/// One `<source>-to-<destination> map:` section of the almanac.
+pub struct Map {
+    pub source: String,
+    pub destination: String,
+    pub lines: Vec<Line>
+}
+
+pub struct Line {
+    pub source_range_start: u64,
+    pub source_range_end: u64,
+    pub mapping: i64
+}
+
+/// Sorts `ranges`, dropping empty ones and merging those that overlap or touch.
+pub fn merge_ranges(mut ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range)
+        }
+    }
+
+    return merged;
+}
+
+/// Sorts segments and joins neighbours that touch and share an offset.
+fn merge_segments(mut segments: Vec<(Range<u64>, i64)>) -> Vec<(Range<u64>, i64)> {
+    segments.retain(|(r, _)| !r.is_empty());
+    segments.sort_by_key(|(r, _)| r.start);
+
+    let mut merged: Vec<(Range<u64>, i64)> = Vec::with_capacity(segments.len());
+    for (range, offset) in segments {
+        match merged.last_mut() {
+            Some((last, last_offset)) if last.end == range.start && *last_offset == offset => last.end = range.end,
+            _ => merged.push((range, offset))
+        }
+    }
+
+    return merged;
+}
+
+fn lines_of(segments: Vec<(Range<u64>, i64)>) -> Vec<Line> {
+    return segments.into_iter()
+        .filter(|(_, offset)| *offset != 0)
+        .map(|(range, offset)| Line {
+            source_range_start: range.start,
+            source_range_end: range.end,
+            mapping: offset
+        })
+        .collect();
+}
+
+/// Moves every value of `range` by `offset`.
+pub(super) fn shift(range: &Range<u64>, offset: i64) -> Range<u64> {
+    let shift = |v: u64| (v as i128 + offset as i128) as u64;
+    return shift(range.start)..shift(range.end);
+}
+
+impl Map {
+    pub fn map(&self, value: u64) -> u64 {
+        return self.matching_line(value).map_or(value, |line| line.map(value));
+    }
+
+    /// The line [`Map::map`] uses for `value`, or `None` if it maps to itself.
+    pub fn matching_line(&self, value: u64) -> Option<&Line> {
+        return self.lines.iter().find(|line| line.matches(value));
+    }
+
+    /// Maps every value of the `[start, end)` intervals in `ranges` at once, splitting
+    /// intervals where lines begin and end. Like [`Map::map`], the first matching line wins
+    /// and values no line matches map to themselves.
+    pub fn map_ranges(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        return merge_ranges(self.split_ranges(ranges).iter()
+            .map(|(range, offset)| shift(range, *offset))
+            .collect());
+    }
+
+    /// Splits `ranges` where lines begin and end, pairing each piece with the offset this
+    /// map adds to all of its values.
+    pub fn split_ranges(&self, ranges: &[Range<u64>]) -> Vec<(Range<u64>, i64)> {
+        let mut unmapped: Vec<Range<u64>> = ranges.to_vec();
+        let mut pieces: Vec<(Range<u64>, i64)> = Vec::new();
+
+        for line in self.lines.iter() {
+            let mut remaining = Vec::new();
+            for range in unmapped {
+                let (inside, outside) = line.split(&range);
+                pieces.extend(inside.map(|r| (r, line.mapping)));
+                remaining.extend(outside);
+            }
+            unmapped = remaining;
+        }
+
+        pieces.extend(unmapped.into_iter().map(|r| (r, 0)));
+        pieces.sort_by_key(|(r, _)| r.start);
+        return pieces;
+    }
+
+    /// Every value this map sends to `value`, in ascending order. Several source ranges
+    /// may lead to the same value, and `value` itself counts when no line matches it.
+    pub fn preimages(&self, value: u64) -> Vec<u64> {
+        let mut candidates: Vec<u64> = self.lines.iter()
+            .filter_map(|line| line.unmap(value))
+            .chain([value])
+            .filter(|&candidate| self.map(candidate) == value)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        return candidates;
+    }
+
+    /// An equivalent map whose lines are sorted, do not overlap and never map a value to itself,
+    /// so it can be queried with [`Map::lookup`].
+    pub fn normalized(&self) -> Map {
+        return Map {
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            lines: lines_of(self.segments())
+        };
+    }
+
+    /// The same map with identity lines filling the gaps between its lines, which must be
+    /// sorted and disjoint as in a normalized map.
+    pub fn with_identity_gaps(&self) -> Map {
+        let mut lines: Vec<Line> = Vec::with_capacity(self.lines.len() * 2);
+        for line in self.lines.iter() {
+            if let Some(end) = lines.last().map(|l: &Line| l.source_range_end).filter(|&end| end < line.source_range_start) {
+                lines.push(Line { source_range_start: end, source_range_end: line.source_range_start, mapping: 0 });
+            }
+            lines.push(Line { ..*line });
+        }
+
+        return Map {
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            lines
+        };
+    }
+
+    /// The map that applies this map and then `next`, as one normalized map.
+    pub fn compose(&self, next: &Map) -> Map {
+        let next_segments = next.segments();
+        let mut segments = Vec::new();
+
+        for (range, offset) in self.segments() {
+            let image = shift(&range, offset);
+            let first = next_segments.partition_point(|(r, _)| r.end <= image.start);
+
+            for (next_range, next_offset) in next_segments[first..].iter().take_while(|(r, _)| r.start < image.end) {
+                let piece = image.start.max(next_range.start)..image.end.min(next_range.end);
+                segments.push((shift(&piece, -offset), offset + next_offset));
+            }
+        }
+
+        return Map {
+            source: self.source.clone(),
+            destination: next.destination.clone(),
+            lines: lines_of(merge_segments(segments))
+        };
+    }
+
+    /// Like [`Map::map`], but finds the line by binary search. Only valid for normalized
+    /// maps, i.e. those returned by [`Map::normalized`] and [`Map::compose`].
+    pub fn lookup(&self, value: u64) -> u64 {
+        let i = self.lines.partition_point(|line| line.source_range_end <= value);
+        return self.lines.get(i)
+            .filter(|line| line.matches(value))
+            .map_or(value, |line| line.map(value));
+    }
+
+    /// Splits `[0, u64::MAX)` into sorted, disjoint ranges, each shifted by a single offset
+    /// (0 where no line matches), giving overlapping lines to the first one as [`Map::map`] does.
+    fn segments(&self) -> Vec<(Range<u64>, i64)> {
+        let mut segments: Vec<(Range<u64>, i64)> = Vec::new();
+        let mut unclaimed: Vec<Range<u64>> = std::iter::once(0..u64::MAX).collect();
+
+        for line in self.lines.iter() {
+            let mut remaining = Vec::new();
+            for range in unclaimed {
+                let (inside, outside) = line.split(&range);
+                segments.extend(inside.map(|r| (r, line.mapping)));
+                remaining.extend(outside);
+            }
+            unclaimed = remaining;
+        }
+
+        segments.extend(unclaimed.into_iter().map(|r| (r, 0)));
+        return merge_segments(segments);
+    }
+
+    /// The `<destination>-to-<source>` map made of inverted lines. It undoes this map
+    /// exactly as long as no two lines share destination values; use [`Map::preimages`]
+    /// when they might.
+    pub fn inverse(&self) -> Map {
+        return Map {
+            source: self.destination.clone(),
+            destination: self.source.clone(),
+            lines: self.lines.iter().map(|line| line.inverse()).collect()
+        };
+    }
+}
+
+impl Line {
+    pub fn matches(&self, value: u64) -> bool {
+        return self.source_range_start <= value && value < self.source_range_end;
+    }
+
+    pub fn map(&self, value: u64) -> u64 {
+        return (value as i64 + self.mapping) as u64;
+    }
+
+    /// The source value this line maps to `value`, if `value` is in its destination range.
+    pub fn unmap(&self, value: u64) -> Option<u64> {
+        let source = value as i128 - self.mapping as i128;
+        return u64::try_from(source).ok().filter(|&source| self.matches(source));
+    }
+
+    pub fn inverse(&self) -> Line {
+        let destination_range_start = self.map(self.source_range_start);
+        return Line {
+            source_range_start: destination_range_start,
+            source_range_end: destination_range_start + (self.source_range_end - self.source_range_start),
+            mapping: -self.mapping
+        };
+    }
+
+    /// Splits `range` into the part this line matches and the parts before and after it.
+    fn split(&self, range: &Range<u64>) -> (Option<Range<u64>>, Vec<Range<u64>>) {
+        let start = range.start.max(self.source_range_start);
+        let end = range.end.min(self.source_range_end);
+
+        if start >= end {
+            return (None, vec![range.clone()]);
+        }
+
+        let outside = [range.start..start, end..range.end].into_iter()
+            .filter(|r| !r.is_empty())
+            .collect();
+        return (Some(start..end), outside);
+    }
+}
+
+/// Formats the map as an almanac section, a header followed by one line per `Line`.
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}-to-{} map:", self.source, self.destination)?;
+        for line in self.lines.iter() {
+            writeln!(f, "{}", line)?;
+        }
+        return Ok(());
+    }
+}
+
+/// Formats the line the way the almanac writes it, `<destination> <source> <length>`.
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} {} {}", self.map(self.source_range_start), self.source_range_start, self.source_range_end - self.source_range_start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day05::map::{merge_ranges, Line, Map};
+
+    fn seed_to_soil() -> Map {
+        return Map {
+            source: "seed".to_string(),
+            destination: "soil".to_string(),
+            lines: vec![
+                Line { source_range_start: 98, source_range_end: 100, mapping: -48 },
+                Line { source_range_start: 50, source_range_end: 98, mapping: 2 }
+            ]
+        };
+    }
+
+    #[test]
+    fn merge_overlapping_and_touching_ranges() {
+        assert_eq!(merge_ranges(vec![10..20, 0..5, 5..7, 15..25, 30..30]), vec![0..7, 10..25]);
+    }
+
+    #[test]
+    fn map_ranges_splits_at_line_boundaries() {
+        let map = seed_to_soil();
+
+        assert_eq!(map.map_ranges(&[40..60, 200..210]), vec![40..50, 52..62, 200..210]);
+        assert_eq!(map.map_ranges(&[95..105, 0..1]), vec![0..1, 50..52, 97..105]);
+    }
+
+    #[test]
+    fn split_ranges_with_offsets() {
+        let map = seed_to_soil();
+        assert_eq!(map.split_ranges(&[95..105, 0..1]), vec![(0..1, 0), (95..98, 2), (98..100, -48), (100..105, 0)]);
+    }
+
+    #[test]
+    fn inverse_undoes_map() {
+        let map = seed_to_soil();
+        let inverse = map.inverse();
+
+        assert_eq!((inverse.source.as_str(), inverse.destination.as_str()), ("soil", "seed"));
+        for seed in [0, 49, 50, 97, 98, 99, 100] {
+            assert_eq!(inverse.map(map.map(seed)), seed);
+        }
+    }
+
+    #[test]
+    fn preimages_of_values_reached_several_ways() {
+        let map = seed_to_soil();
+
+        assert_eq!(map.preimages(51), vec![99]);
+        assert_eq!(map.preimages(52), vec![50]);
+        // 50 itself is moved by the second line, so only 98 leads to it
+        assert_eq!(map.preimages(50), vec![98]);
+        assert_eq!(map.preimages(10), vec![10]);
+
+        let overlapping = Map {
+            lines: vec![Line { source_range_start: 0, source_range_end: 5, mapping: 10 }],
+            ..seed_to_soil()
+        };
+        assert_eq!(overlapping.preimages(12), vec![2, 12]);
+        assert_eq!(overlapping.preimages(3), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn normalized_resolves_overlaps_in_line_order() {
+        let map = Map {
+            lines: vec![
+                Line { source_range_start: 10, source_range_end: 20, mapping: 5 },
+                Line { source_range_start: 0, source_range_end: 15, mapping: -1 },
+                Line { source_range_start: 30, source_range_end: 40, mapping: 0 }
+            ],
+            ..seed_to_soil()
+        };
+        let normalized = map.normalized();
+
+        let ranges: Vec<(u64, u64, i64)> = normalized.lines.iter()
+            .map(|l| (l.source_range_start, l.source_range_end, l.mapping))
+            .collect();
+        assert_eq!(ranges, vec![(0, 10, -1), (10, 20, 5)]);
+        for value in 0..50 {
+            assert_eq!(normalized.lookup(value), map.map(value));
+        }
+    }
+
+    #[test]
+    fn compose_applies_both_maps() {
+        let first = seed_to_soil();
+        let second = Map {
+            source: "soil".to_string(),
+            destination: "fertilizer".to_string(),
+            lines: vec![
+                Line { source_range_start: 15, source_range_end: 52, mapping: -15 },
+                Line { source_range_start: 52, source_range_end: 54, mapping: -15 },
+                Line { source_range_start: 0, source_range_end: 15, mapping: 39 }
+            ]
+        };
+        let composed = first.compose(&second);
+
+        assert_eq!((composed.source.as_str(), composed.destination.as_str()), ("seed", "fertilizer"));
+        assert!(composed.lines.windows(2).all(|w| w[0].source_range_end <= w[1].source_range_start));
+        for seed in 0..120 {
+            assert_eq!(composed.lookup(seed), second.map(first.map(seed)));
+        }
+    }
+
+    #[test]
+    fn identity_gaps() {
+        let map = seed_to_soil().normalized().with_identity_gaps();
+        assert_eq!(map.to_string(), "seed-to-soil map:\n52 50 48\n50 98 2\n");
+
+        let gappy = Map {
+            lines: vec![
+                Line { source_range_start: 0, source_range_end: 5, mapping: 10 },
+                Line { source_range_start: 8, source_range_end: 10, mapping: -8 }
+            ],
+            ..seed_to_soil()
+        };
+        assert_eq!(gappy.with_identity_gaps().to_string(), "seed-to-soil map:\n10 0 5\n5 5 3\n0 8 2\n");
+    }
+
+    #[test]
+    fn unmap_values_above_i64_max() {
+        let line = Line { source_range_start: 9_000_000_000_000_000_000, source_range_end: 9_000_000_000_000_000_010, mapping: -9_000_000_000_000_000_000 };
+        assert_eq!(line.unmap(5), Some(9_000_000_000_000_000_005));
+        assert_eq!(line.unmap(9_000_000_000_000_000_000), None);
+        assert_eq!(seed_to_soil().preimages(1 << 63), vec![1 << 63]);
+    }
+}
@@ -1,155 +1,292 @@
 use std::io::BufRead;
 
-use rayon::prelude::*;
+use crate::{downcast, Answer, AocError, Model, Solution, error::parse_number};
 
-use crate::read_input;
+mod almanac;
+mod check;
+mod generate;
+mod map;
 
-pub fn what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(input: &mut dyn BufRead) -> u64 {
-    let lines = read_input(input);
-    let mut parts = lines.split(|line| line == "");
-    let seeds = parse_seeds(parts.next().unwrap());
-    let maps = parts.map(|part| parse_map(part)).collect::<Vec<Map>>();
+pub use almanac::{Almanac, Step, MAX_HISTOGRAM_BUCKETS};
+pub use check::{check, Issue, Problem};
+pub use generate::{generate, GeneratorConfig};
+pub use map::{merge_ranges, Line, Map};
 
-    return seeds.iter().map(|s| get_location(*s, &maps))
-        .min()
-        .unwrap();
-}
+pub struct Day05;
 
-pub fn what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(input: &mut dyn BufRead) -> u64 {
-    let lines = read_input(input);
-    let mut parts = lines.split(|line| line == "");
-    let seeds = parse_seeds_ranges(parts.next().unwrap());
-    let maps = parts.map(|part| parse_map(part)).collect::<Vec<Map>>();
+impl Solution for Day05 {
+    fn day(&self) -> u8 {
+        return 5;
+    }
 
-    return seeds.par_iter()
-        .map(|s| get_location(*s, &maps))
-        .min()
-        .unwrap();
+    fn title(&self) -> &'static str {
+        return "If You Give A Seed A Fertilizer";
+    }
+
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError> {
+        return Ok(Box::new(parse(input)?));
+    }
+
+    fn part1(&self, model: &Model) -> Result<Answer, AocError> {
+        return part1(downcast::<Almanac>(model)?).map(Answer::from);
+    }
+
+    fn part2(&self, model: &Model) -> Result<Answer, AocError> {
+        return part2(downcast::<Almanac>(model)?).map(Answer::from);
+    }
 }
 
-fn parse_seeds(lines: &[String]) -> Vec<u64> {
-    return lines.get(0).unwrap()
-        .split_once("seeds: ").unwrap().1
-        .split_whitespace()
-        .map(|n| n.parse::<u64>().unwrap())
-        .collect();
+pub fn parse(input: &mut dyn BufRead) -> Result<Almanac, AocError> {
+    let mut seeds = Vec::new();
+    let mut maps: Vec<Map> = Vec::new();
+
+    read_almanac(input, |line_number, event| {
+        match event {
+            Event::Seeds(numbers) => seeds = numbers,
+            Event::Header(source, destination) => maps.push(Map { source, destination, lines: Vec::new() }),
+            Event::Entry(destination, source, length) => {
+                let line = to_line(line_number, destination, source, length)?;
+                maps.last_mut().expect("entries follow a header").lines.push(line);
+            }
+        }
+        return Ok(());
+    })?;
+
+    return Ok(Almanac {
+        seeds,
+        maps
+    });
 }
 
-fn parse_seeds_ranges(lines: &[String]) -> Vec<u64> {
-    let ranges = lines.get(0).unwrap()
-        .split_once("seeds: ").unwrap().1
-        .split_whitespace()
-        .map(|n| n.parse::<u64>().unwrap())
-        .collect::<Vec<u64>>()
-        .chunks(2)
-        .map(|chunk| (chunk[0], chunk[1]))
-        .collect::<Vec<(u64, u64)>>();
-    
-    return ranges.par_iter()
-        .flat_map(|(start, length)| (*start..start+length))
-        .collect();
+pub fn part1(almanac: &Almanac) -> Result<u64, AocError> {
+    return almanac.seeds.iter().map(|s| almanac.get_location(*s))
+        .collect::<Result<Vec<u64>, AocError>>()?
+        .into_iter()
+        .min()
+        .ok_or_else(|| AocError::InvalidValue("no seeds".to_string()));
 }
 
-fn parse_map(lines: &[String]) -> Map {
-    let map_lines = lines.iter()
-        .skip(1)
-        .map(|line| parse_map_line(line))
-        .collect();
+pub fn part2(almanac: &Almanac) -> Result<u64, AocError> {
+    let seeds = almanac.seed_ranges()?;
+    let locations = almanac.map_ranges("seed", "location", seeds)?;
 
-    return Map {
-        lines: map_lines
-    }
+    return locations.first()
+        .map(|range| range.start)
+        .ok_or_else(|| AocError::InvalidValue("no seeds".to_string()));
 }
 
-fn parse_map_line(line: &String) -> Line {
-    let numbers = line.split_whitespace()
-        .map(|n| n.parse::<u64>().unwrap())
-        .collect::<Vec<u64>>();
+pub fn what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part1(&parse(input)?);
+}
 
-    let destination_range_start = *numbers.get(0).unwrap();
-    let source_range_start = *numbers.get(1).unwrap();
-    let range_length = *numbers.get(2).unwrap();
+pub fn what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(input: &mut dyn BufRead) -> Result<u64, AocError> {
+    return part2(&parse(input)?);
+}
 
-    return Line {
-        source_range_start: source_range_start,
-        source_range_end: source_range_start + range_length,
-        mapping: destination_range_start as i64 - source_range_start as i64
-    }
+/// One piece of an almanac, as read by [`read_almanac`].
+enum Event {
+    Seeds(Vec<u64>),
+    Header(String, String),
+    /// A `<destination> <source> <length>` map line.
+    Entry(u64, u64, u64)
 }
 
-fn get_location(seed: u64, maps: &Vec<Map>) -> u64 {
-    let mut index = 0;
-    let mut value = seed;
+enum State {
+    Seeds,
+    Header,
+    Entries
+}
+
+/// Reads an almanac one line at a time, handing each piece and its 1-based line number to
+/// `on_event` as soon as it is read. Tolerates `\r\n` line endings, trailing whitespace and
+/// any number of blank lines between sections. Parse errors name the section they occur in.
+fn read_almanac(input: &mut dyn BufRead, mut on_event: impl FnMut(usize, Event) -> Result<(), AocError>) -> Result<(), AocError> {
+    let mut state = State::Seeds;
+    let mut section = "seeds".to_string();
+    let mut buffer = String::new();
+    let mut line_number = 0;
+
     loop {
-        if let Some(map) = maps.get(index) {
-            index += 1;
-            value = map.map(value);
+        buffer.clear();
+        if input.read_line(&mut buffer)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let line = buffer.trim_end();
 
-            if index == 7 {
-                return value;
+        let event = match state {
+            _ if line.is_empty() => {
+                if let State::Entries = state {
+                    state = State::Header;
+                }
+                continue;
+            },
+            State::Seeds => {
+                state = State::Header;
+                Event::Seeds(parse_seeds(line_number, line).map_err(|e| in_section("seeds", e))?)
+            },
+            State::Header => {
+                let (source, destination) = parse_map_header(line_number, line).map_err(|e| in_section("map header", e))?;
+                section = format!("{}-to-{} map", source, destination);
+                state = State::Entries;
+                Event::Header(source, destination)
+            },
+            State::Entries => {
+                let (destination, source, length) = parse_map_numbers(line_number, line).map_err(|e| in_section(&section, e))?;
+                Event::Entry(destination, source, length)
             }
-        }
-        else {
-            panic!("Map {} not found", index);
-        }
+        };
+
+        on_event(line_number, event).map_err(|e| in_section(&section, e))?;
+    }
+
+    if let State::Seeds = state {
+        return Err(AocError::MissingSection("seeds".to_string()));
     }
+    return Ok(());
 }
 
-struct Map {
-    lines: Vec<Line>
+fn in_section(section: &str, error: AocError) -> AocError {
+    return match error {
+        AocError::Parse { line, column, message } => AocError::Parse { line, column, message: format!("{}: {}", section, message) },
+        error => error
+    };
 }
 
-struct Line {
-    source_range_start: u64,
-    source_range_end: u64,
-    mapping: i64
+fn parse_seeds(line_number: usize, line: &str) -> Result<Vec<u64>, AocError> {
+    return line
+        .strip_prefix("seeds:").ok_or_else(|| AocError::parse(line_number, 1, "expected 'seeds: '"))?
+        .split_whitespace()
+        .map(|n| parse_number(line_number, line, n))
+        .collect();
 }
 
-impl Map {
-    fn map(&self, value: u64) -> u64 {
-        return self.lines.iter()
-            .find(|line| line.matches(value))
-            .map_or(value, |line| line.map(value));
-    }
+fn parse_map_header(line_number: usize, line: &str) -> Result<(String, String), AocError> {
+    return line.strip_suffix(" map:")
+        .and_then(|categories| categories.split_once("-to-"))
+        .filter(|(source, destination)| !source.is_empty() && !destination.is_empty())
+        .map(|(source, destination)| (source.to_string(), destination.to_string()))
+        .ok_or_else(|| AocError::parse(line_number, 1, "expected '<source>-to-<destination> map:'"));
 }
 
-impl Line {
-    fn matches(&self, value: u64) -> bool {
-        return self.source_range_start <= value && value < self.source_range_end;
+fn to_line(line_number: usize, destination_range_start: u64, source_range_start: u64, range_length: u64) -> Result<Line, AocError> {
+    if check::overflows(destination_range_start, source_range_start, range_length) {
+        return Err(AocError::parse(line_number, 1, "range does not fit in i64"));
     }
 
-    fn map(&self, value: u64) -> u64 {
-        return (value as i64 + self.mapping) as u64;
+    return Ok(Line {
+        source_range_start,
+        source_range_end: source_range_start + range_length,
+        mapping: destination_range_start as i64 - source_range_start as i64
+    });
+}
+
+/// Reads the `<destination> <source> <length>` numbers of a map line.
+fn parse_map_numbers(line_number: usize, line: &str) -> Result<(u64, u64, u64), AocError> {
+    let numbers = line.split_whitespace()
+        .map(|n| parse_number(line_number, line, n))
+        .collect::<Result<Vec<u64>, AocError>>()?;
+
+    if numbers.len() != 3 {
+        return Err(AocError::parse(line_number, 1, format!("expected '<destination> <source> <length>', found {} numbers", numbers.len())));
     }
+
+    return Ok((numbers[0], numbers[1], numbers[2]));
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{io::BufReader, fs::File};
+    use std::fs;
 
-    use crate::day05::{what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers, what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges};
+    use crate::day05::{parse, part1, part2, what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers, what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges};
+    use crate::input::{open, InputResolver};
 
     #[test]
     fn sample_part1_input() {
-        let mut f = BufReader::new(File::open("./src/day05/sample.input").unwrap());
-        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut f), 35);
+        let mut f = open(5, "sample").unwrap();
+        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut f).unwrap(), 35);
     }
 
     #[test]
     fn part1_input() {
-        let mut f = BufReader::new(File::open("./src/day05/my.input").unwrap());
-        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut f), 173706076);
+        let mut f = open(5, "my").unwrap();
+        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut f).unwrap(), 173706076);
     }
 
     #[test]
     fn sample_part2_input() {
-        let mut f = BufReader::new(File::open("./src/day05/sample.input").unwrap());
-        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(&mut f), 46);
+        let mut f = open(5, "sample").unwrap();
+        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(&mut f).unwrap(), 46);
     }
 
     #[test]
     fn part2_input() {
-        let mut f = BufReader::new(File::open("./src/day05/my.input").unwrap());
-        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(&mut f), 11611182);
+        let mut f = open(5, "my").unwrap();
+        assert_eq!(what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_ranges(&mut f).unwrap(), 11611182);
+    }
+
+    #[test]
+    fn malformed_map_line() {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n52 50\n";
+        let error = what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut input.as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 5, column 1: seed-to-soil map: expected '<destination> <source> <length>', found 2 numbers");
+    }
+
+    #[test]
+    fn missing_maps() {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n";
+        let error = what_is_the_lowest_location_number_that_corresponds_to_any_of_the_initial_seed_numbers(&mut input.as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "missing section: maps from 'seed' to 'location'");
+    }
+
+    #[test]
+    fn parse_once_solve_both_parts() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        assert_eq!(almanac.seeds, vec![79, 14, 55, 13]);
+        assert_eq!(almanac.maps.len(), 7);
+        assert_eq!(part1(&almanac).unwrap(), 35);
+        assert_eq!(part2(&almanac).unwrap(), 46);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn map_headers() {
+        let almanac = parse(&mut open(5, "sample").unwrap()).unwrap();
+        let links: Vec<(&str, &str)> = almanac.maps.iter().map(|m| (m.source.as_str(), m.destination.as_str())).collect();
+        assert_eq!(links[0], ("seed", "soil"));
+        assert_eq!(links[6], ("humidity", "location"));
+    }
+
+    #[test]
+    fn malformed_map_header() {
+        let input = "seeds: 79 14\n\nseed-soil map:\n50 98 2\n";
+        let error = parse(&mut input.as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 3, column 1: map header: expected '<source>-to-<destination> map:'");
+    }
+
+    #[test]
+    fn range_beyond_i64() {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 9223372036854775800 10\n";
+        let error = parse(&mut input.as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 4, column 1: seed-to-soil map: range does not fit in i64");
+    }
+
+    #[test]
+    fn tolerant_of_line_endings_and_blank_lines() {
+        let text = fs::read_to_string(InputResolver::from_env().repository_input(5, "sample")).unwrap();
+        let messy = text.replace("\n\n", "\n\n\n  \n").replace('\n', " \t\r\n");
+        let almanac = parse(&mut format!("\r\n{}", messy).as_bytes()).unwrap();
+
+        assert_eq!(almanac.to_string(), format!("{}\n", text.trim_end()));
+    }
+
+    #[test]
+    fn errors_name_the_section() {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n\nsoil-to-water map:\n1 2 x\n";
+        let error = parse(&mut input.as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 7, column 5: soil-to-water map: invalid number 'x'");
+
+        let error = parse(&mut "seed: 1 2\n".as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "line 1, column 1: seeds: expected 'seeds: '");
+        assert_eq!(parse(&mut "\n\n".as_bytes()).err().unwrap().to_string(), "missing section: seeds");
+    }
+}
@@ -0,0 +1,80 @@
+use std::{fmt, io, str::FromStr};
+
+#[derive(Debug)]
+pub enum AocError {
+    Io(io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String
+    },
+    MissingSection(String),
+    InvalidValue(String),
+    Fetch(String)
+}
+
+impl AocError {
+    pub fn parse(line: usize, column: usize, message: impl Into<String>) -> AocError {
+        return AocError::Parse {
+            line,
+            column,
+            message: message.into()
+        };
+    }
+}
+
+impl fmt::Display for AocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            AocError::Io(e) => write!(f, "I/O error: {}", e),
+            AocError::Parse { line, column, message } => write!(f, "line {}, column {}: {}", line, column, message),
+            AocError::MissingSection(section) => write!(f, "missing section: {}", section),
+            AocError::InvalidValue(message) => write!(f, "invalid value: {}", message),
+            AocError::Fetch(message) => write!(f, "fetch failed: {}", message)
+        };
+    }
+}
+
+impl std::error::Error for AocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            AocError::Io(e) => Some(e),
+            _ => None
+        };
+    }
+}
+
+impl From<io::Error> for AocError {
+    fn from(e: io::Error) -> AocError {
+        return AocError::Io(e);
+    }
+}
+
+/// 1-based column at which `fragment`, a subslice of `line`, starts.
+pub(crate) fn column_of(line: &str, fragment: &str) -> usize {
+    return fragment.as_ptr() as usize - line.as_ptr() as usize + 1;
+}
+
+/// Parses `raw`, a subslice of `line`, reporting its position on failure.
+pub(crate) fn parse_number<T: FromStr>(line_number: usize, line: &str, raw: &str) -> Result<T, AocError> {
+    return raw.parse::<T>()
+        .map_err(|_| AocError::parse(line_number, column_of(line, raw), format!("invalid number '{}'", raw)));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{AocError, column_of};
+
+    #[test]
+    fn parse_error_reports_line_and_column() {
+        let error = AocError::parse(17, 12, "expected '|'");
+        assert_eq!(error.to_string(), "line 17, column 12: expected '|'");
+    }
+
+    #[test]
+    fn column_of_subslice() {
+        let line = "Card 1: 41 48";
+        assert_eq!(column_of(line, &line[8..]), 9);
+        assert_eq!(column_of(line, line), 1);
+    }
+}
@@ -0,0 +1,222 @@
+use std::{env, fs, path::PathBuf, sync::Mutex, thread, time::{Duration, Instant}};
+
+use crate::{input::InputResolver, AocError};
+
+pub const SESSION_VAR: &str = "AOC_SESSION";
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+const USER_AGENT: &str = "github.com/mjedwabn/aoc2023 input fetcher";
+
+pub trait Fetcher {
+    fn fetch(&self, day: u8) -> Result<String, AocError>;
+}
+
+/// Downloads `<base_url>/2023/day/N/input` with the given session cookie,
+/// waiting at least `min_interval` between consecutive requests.
+pub struct HttpFetcher {
+    base_url: String,
+    session: String,
+    min_interval: Duration,
+    last_request: Mutex<Option<Instant>>,
+    agent: ureq::Agent
+}
+
+impl HttpFetcher {
+    pub fn new(base_url: &str, session: &str) -> HttpFetcher {
+        return HttpFetcher {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: session.trim().to_string(),
+            min_interval: DEFAULT_MIN_INTERVAL,
+            last_request: Mutex::new(None),
+            agent: ureq::AgentBuilder::new()
+                .user_agent(USER_AGENT)
+                .timeout(Duration::from_secs(30))
+                .build()
+        };
+    }
+
+    /// Reads the session token from `$AOC_SESSION` and the base URL from `$AOC_BASE_URL`.
+    pub fn from_env() -> Result<HttpFetcher, AocError> {
+        let session = env::var(SESSION_VAR)
+            .map_err(|_| AocError::Fetch(format!("${} is not set", SESSION_VAR)))?;
+        let base_url = env::var(BASE_URL_VAR).unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        return Ok(HttpFetcher::new(&base_url, &session));
+    }
+
+    pub fn with_min_interval(self, min_interval: Duration) -> HttpFetcher {
+        return HttpFetcher {
+            min_interval,
+            ..self
+        };
+    }
+
+    pub fn url(&self, day: u8) -> String {
+        return format!("{}/2023/day/{}/input", self.base_url, day);
+    }
+
+    fn throttle(&self) {
+        let mut last_request = self.last_request.lock().unwrap();
+        if let Some(wait) = last_request.and_then(|last| self.min_interval.checked_sub(last.elapsed())) {
+            thread::sleep(wait);
+        }
+        *last_request = Some(Instant::now());
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, day: u8) -> Result<String, AocError> {
+        self.throttle();
+
+        let url = self.url(day);
+        let response = self.agent.get(&url)
+            .set("Cookie", &format!("session={}", self.session))
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(code, _) => AocError::Fetch(format!("{} returned HTTP {}", url, code)),
+                e => AocError::Fetch(format!("{}: {}", url, e))
+            })?;
+
+        return response.into_string()
+            .map_err(|e| AocError::Fetch(format!("{}: {}", url, e)));
+    }
+}
+
+/// Puzzle inputs downloaded for one user, stored where [`InputResolver`] looks for them.
+/// An input that is already cached is never fetched again.
+pub struct InputCache {
+    resolver: InputResolver,
+    user: String
+}
+
+impl InputCache {
+    pub fn new(resolver: &InputResolver) -> Result<InputCache, AocError> {
+        if resolver.input_dir().is_none() {
+            return Err(AocError::Fetch(format!("${} is not set", crate::input::INPUT_DIR_VAR)));
+        }
+        let user = resolver.user()
+            .ok_or_else(|| AocError::Fetch(format!("no user given and ${} is not set", crate::input::USER_VAR)))?;
+
+        return Ok(InputCache {
+            resolver: resolver.clone(),
+            user: user.to_string()
+        });
+    }
+
+    pub fn path(&self, day: u8) -> PathBuf {
+        return self.resolver.user_input(day, &self.user).unwrap();
+    }
+
+    pub fn contains(&self, day: u8) -> bool {
+        return self.path(day).is_file();
+    }
+
+    /// Returns the cached input path, fetching it first if needed, and whether a fetch happened.
+    pub fn get_or_fetch(&self, day: u8, fetcher: &dyn Fetcher) -> Result<(PathBuf, bool), AocError> {
+        let path = self.path(day);
+        if path.is_file() {
+            return Ok((path, false));
+        }
+
+        let content = fetcher.fetch(day)?;
+        if content.trim().is_empty() {
+            return Err(AocError::Fetch(format!("empty input for day {}", day)));
+        }
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        let partial = path.with_extension("part");
+        fs::write(&partial, content)?;
+        fs::rename(&partial, &path)?;
+
+        return Ok((path, true));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::{BufRead, BufReader, Write}, net::TcpListener, sync::mpsc, thread, time::{Duration, Instant}};
+
+    use crate::{fetch::{Fetcher, HttpFetcher, InputCache}, input::InputResolver};
+
+    /// Serves `responses` in order on a local port, reporting each request line and cookie.
+    fn mock_server(responses: Vec<(u16, &'static str)>) -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut cookie = String::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.strip_prefix("Cookie: ") {
+                        cookie = value.trim().to_string();
+                    }
+                }
+
+                sender.send((request_line.trim().to_string(), cookie)).unwrap();
+                write!(stream, "HTTP/1.1 {} OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body).unwrap();
+            }
+        });
+
+        return (address, receiver);
+    }
+
+    #[test]
+    fn fetches_with_session_cookie() {
+        let (address, requests) = mock_server(vec![(200, "seeds: 79 14 55 13\n")]);
+        let fetcher = HttpFetcher::new(&address, "secret\n");
+
+        assert_eq!(fetcher.fetch(5).unwrap(), "seeds: 79 14 55 13\n");
+        assert_eq!(requests.recv().unwrap(), ("GET /2023/day/5/input HTTP/1.1".to_string(), "session=secret".to_string()));
+    }
+
+    #[test]
+    fn reports_http_errors() {
+        let (address, _requests) = mock_server(vec![(400, "Puzzle inputs differ by user.  Please log in to get your puzzle input.")]);
+        let fetcher = HttpFetcher::new(&address, "expired");
+
+        assert!(fetcher.fetch(1).unwrap_err().to_string().ends_with("returned HTTP 400"));
+    }
+
+    #[test]
+    fn waits_between_requests() {
+        let (address, _requests) = mock_server(vec![(200, "a"), (200, "b")]);
+        let fetcher = HttpFetcher::new(&address, "secret").with_min_interval(Duration::from_millis(300));
+
+        let start = Instant::now();
+        fetcher.fetch(1).unwrap();
+        fetcher.fetch(2).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[test]
+    fn cached_inputs_are_never_fetched_again() {
+        let dir = env::temp_dir().join(format!("aoc2023rust-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let (address, requests) = mock_server(vec![(200, "Card 1: 1 | 1\n")]);
+        let fetcher = HttpFetcher::new(&address, "secret");
+        let resolver = InputResolver::new(Some(dir.clone()), Some("alice".to_string()));
+        let cache = InputCache::new(&resolver).unwrap();
+
+        let (path, fetched) = cache.get_or_fetch(4, &fetcher).unwrap();
+        assert!(fetched);
+        assert_eq!(path, dir.join("alice").join("day04.txt"));
+        assert_eq!(resolver.puzzle_input(4), path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Card 1: 1 | 1\n");
+
+        assert_eq!(cache.get_or_fetch(4, &fetcher).unwrap(), (path, false));
+        assert_eq!(requests.iter().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::{fmt, io::BufRead};
+
+use crate::{read_input, AocError};
+
+/// A `(x, y)` position, `x` counting columns and `y` counting rows from the top left.
+pub type Coord = (usize, usize);
+
+/// A dense, rectangular 2D grid stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from rows that must all have the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, AocError> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            return Err(AocError::InvalidValue(format!("row {} has {} columns, expected {}", y + 1, rows[y].len(), width)));
+        }
+
+        return Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect()
+        });
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        return coord.0 < self.width && coord.1 < self.height;
+    }
+
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        return self.index(coord).map(|i| &self.cells[i]);
+    }
+
+    pub fn get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+        return self.index(coord).map(|i| &mut self.cells[i]);
+    }
+
+    fn index(&self, coord: Coord) -> Option<usize> {
+        return self.contains(coord).then(|| coord.1 * self.width + coord.0);
+    }
+
+    /// Every position, row by row.
+    pub fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
+        return (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)));
+    }
+
+    /// The positions above, below, left and right of `coord` that are inside the grid.
+    pub fn neighbours4(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        return self.offsets(coord, &[(0, -1), (-1, 0), (1, 0), (0, 1)]);
+    }
+
+    /// The positions around `coord`, diagonals included, that are inside the grid.
+    pub fn neighbours8(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        return self.offsets(coord, &[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]);
+    }
+
+    fn offsets(&self, coord: Coord, offsets: &'static [(isize, isize)]) -> impl Iterator<Item = Coord> + '_ {
+        return offsets.iter()
+            .filter_map(move |(dx, dy)| Some((coord.0.checked_add_signed(*dx)?, coord.1.checked_add_signed(*dy)?)))
+            .filter(|&c| self.contains(c));
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        return &self.cells[y * self.width..(y + 1) * self.width];
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        return (0..self.height).map(|y| self.row(y));
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        return self.cells.iter().skip(x).step_by(self.width.max(1)).take(self.height);
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        return (0..self.width).map(|x| self.column(x));
+    }
+
+    /// A grid of the same shape with `f` applied to every cell.
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        return Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect()
+        };
+    }
+}
+
+impl Grid<char> {
+    /// Reads one row per line, one cell per character. Blank lines at the end are ignored.
+    pub fn parse(input: &mut dyn BufRead) -> Result<Grid<char>, AocError> {
+        let mut lines = read_input(input)?;
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let row: Vec<char> = line.chars().collect();
+            if let Some(width) = rows.first().map(|first| first.len()).filter(|&width| width != row.len()) {
+                return Err(AocError::parse(i + 1, width.min(row.len()) + 1, format!("expected {} columns, found {}", width, row.len())));
+            }
+            rows.push(row);
+        }
+        return Grid::from_rows(rows);
+    }
+}
+
+/// Prints every row on its own line.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::Grid;
+
+    fn sample() -> Grid<char> {
+        return Grid::parse(&mut "abc\ndef\n".as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let grid = sample();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get((2, 1)), Some(&'f'));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+    }
+
+    #[test]
+    fn ragged_rows() {
+        let error = Grid::parse(&mut "abc\nde\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 3: expected 3 columns, found 2");
+        assert!(Grid::from_rows(vec![vec![1], vec![]]).is_err());
+
+        let error = Grid::parse(&mut "abc\n\nabc\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 1: expected 3 columns, found 0");
+    }
+
+    #[test]
+    fn trailing_blank_lines() {
+        assert_eq!(Grid::parse(&mut "abc\ndef\n\n\n".as_bytes()).unwrap(), sample());
+        assert_eq!(Grid::parse(&mut "\n".as_bytes()).unwrap().height(), 0);
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = sample();
+        assert_eq!(grid.neighbours4((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours8((1, 0)).collect::<Vec<_>>(), vec![(0, 0), (2, 0), (0, 1), (1, 1), (2, 1)]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = sample();
+        let rows: Vec<String> = grid.rows().map(|row| row.iter().collect()).collect();
+        let columns: Vec<String> = grid.columns().map(|column| column.collect()).collect();
+
+        assert_eq!(rows, vec!["abc", "def"]);
+        assert_eq!(columns, vec!["ad", "be", "cf"]);
+        assert_eq!(grid.coords().nth(4), Some((1, 1)));
+    }
+
+    #[test]
+    fn map_and_get_mut() {
+        let mut grid = sample().map(|c| c.is_ascii_lowercase() && *c != 'e');
+        *grid.get_mut((0, 0)).unwrap() = false;
+        assert_eq!(grid.to_string(), "falsetruetrue\ntruefalsetrue\n");
+        assert_eq!(Grid::<u8>::from_rows(vec![]).unwrap().to_string(), "");
+    }
+}
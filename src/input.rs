@@ -0,0 +1,153 @@
+use std::{env, fs::{self, File}, io::BufReader, path::{Path, PathBuf}};
+
+use crate::AocError;
+
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+pub const USER_VAR: &str = "AOC_USER";
+
+/// Locates puzzle inputs. Per-user inputs live at `$AOC_INPUT_DIR/<user>/dayNN.txt`;
+/// inputs committed to the repository live at `src/dayNN/<name>.input`.
+#[derive(Debug, Clone)]
+pub struct InputResolver {
+    input_dir: Option<PathBuf>,
+    user: Option<String>,
+    repository_dir: PathBuf
+}
+
+impl InputResolver {
+    pub fn new(input_dir: Option<PathBuf>, user: Option<String>) -> InputResolver {
+        return InputResolver {
+            input_dir,
+            user,
+            repository_dir: Path::new(env!("CARGO_MANIFEST_DIR")).join("src")
+        };
+    }
+
+    /// Reads `$AOC_INPUT_DIR` and the user from `$AOC_USER`, falling back to `$USER`.
+    pub fn from_env() -> InputResolver {
+        let input_dir = env::var_os(INPUT_DIR_VAR).map(PathBuf::from);
+        let user = env::var(USER_VAR).or_else(|_| env::var("USER")).ok();
+        return InputResolver::new(input_dir, user);
+    }
+
+    pub fn with_user(self, user: Option<String>) -> InputResolver {
+        return InputResolver {
+            user: user.or(self.user),
+            ..self
+        };
+    }
+
+    pub fn input_dir(&self) -> Option<&Path> {
+        return self.input_dir.as_deref();
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        return self.user.as_deref();
+    }
+
+    /// Where the given user's input for `day` is kept, whether or not it exists yet.
+    pub fn user_input(&self, day: u8, user: &str) -> Option<PathBuf> {
+        return self.input_dir.as_ref().map(|dir| dir.join(user).join(format!("day{:02}.txt", day)));
+    }
+
+    pub fn repository_input(&self, day: u8, name: &str) -> PathBuf {
+        return self.repository_dir.join(format!("day{:02}", day)).join(format!("{}.input", name));
+    }
+
+    /// The current user's input if they have one, otherwise the repository's `my.input`.
+    pub fn puzzle_input(&self, day: u8) -> PathBuf {
+        return self.user.as_deref()
+            .and_then(|user| self.user_input(day, user))
+            .filter(|path| path.is_file())
+            .unwrap_or_else(|| self.repository_input(day, "my"));
+    }
+
+    /// A named input: a repository input such as `sample`, or else the input of the user with that name.
+    pub fn find(&self, day: u8, name: &str) -> Option<PathBuf> {
+        let repository_input = self.repository_input(day, name);
+        if repository_input.is_file() {
+            return Some(repository_input);
+        }
+        return self.user_input(day, name).filter(|path| path.is_file());
+    }
+
+    /// Every named input for `day`: the repository's inputs followed by each user's input.
+    pub fn inputs(&self, day: u8) -> Vec<(String, PathBuf)> {
+        let mut repository_inputs: Vec<(String, PathBuf)> = list_dir(&self.repository_dir.join(format!("day{:02}", day)))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "input"))
+            .filter_map(|path| file_stem(&path).map(|name| (name, path)))
+            .collect();
+        repository_inputs.sort();
+
+        let mut user_inputs: Vec<(String, PathBuf)> = self.input_dir.iter()
+            .flat_map(|dir| list_dir(dir))
+            .filter_map(|user_dir| file_name(&user_dir).map(|user| (user, user_dir.join(format!("day{:02}.txt", day)))))
+            .filter(|(_, path)| path.is_file())
+            .collect();
+        user_inputs.sort();
+
+        repository_inputs.extend(user_inputs);
+        return repository_inputs;
+    }
+}
+
+/// Opens a named input for `day`, see [`InputResolver::find`].
+pub fn open(day: u8, name: &str) -> Result<BufReader<File>, AocError> {
+    let path = InputResolver::from_env().find(day, name)
+        .ok_or_else(|| AocError::MissingSection(format!("input '{}' for day {}", name, day)))?;
+    return Ok(BufReader::new(File::open(path)?));
+}
+
+fn list_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    return fs::read_dir(dir).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()));
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    return path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string());
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    return path.file_name().and_then(|s| s.to_str()).map(|s| s.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use crate::input::InputResolver;
+
+    fn temp_input_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoc2023rust-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("alice")).unwrap();
+        fs::create_dir_all(dir.join("bob")).unwrap();
+        fs::write(dir.join("alice").join("day05.txt"), "seeds: 1 2\n").unwrap();
+        return dir;
+    }
+
+    #[test]
+    fn puzzle_input_prefers_user_input() {
+        let dir = temp_input_dir("puzzle-input");
+        let alice = InputResolver::new(Some(dir.clone()), Some("alice".to_string()));
+        let bob = alice.clone().with_user(Some("bob".to_string()));
+
+        assert_eq!(alice.puzzle_input(5), dir.join("alice").join("day05.txt"));
+        assert!(bob.puzzle_input(5).ends_with("src/day05/my.input"));
+        assert!(alice.puzzle_input(4).ends_with("src/day04/my.input"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn inputs_include_every_user() {
+        let dir = temp_input_dir("inputs");
+        let resolver = InputResolver::new(Some(dir.clone()), None);
+
+        let names: Vec<String> = resolver.inputs(5).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["my", "sample", "alice"]);
+        assert_eq!(resolver.find(5, "alice"), Some(dir.join("alice").join("day05.txt")));
+        assert_eq!(resolver.find(5, "bob"), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
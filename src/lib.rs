@@ -1,11 +1,100 @@
-use std::io::BufRead;
+#![allow(clippy::needless_return)]
+
+use std::{any::Any, io::BufRead};
 
 pub mod day01;
 pub mod day02;
 pub mod day03;
 pub mod day04;
 pub mod day05;
+pub mod answers;
+pub mod bench;
+pub mod fetch;
+pub mod grid;
+pub mod input;
+mod answer;
+mod error;
+
+pub use answer::Answer;
+pub use error::AocError;
+
+/// A day's parsed input, as returned by [`Solution::parse`].
+pub type Model = Box<dyn Any + Send + Sync>;
+
+pub trait Solution: Sync {
+    fn day(&self) -> u8;
+    fn title(&self) -> &'static str;
+    fn parse(&self, input: &mut dyn BufRead) -> Result<Model, AocError>;
+    fn part1(&self, model: &Model) -> Result<Answer, AocError>;
+    fn part2(&self, model: &Model) -> Result<Answer, AocError>;
+
+    fn solve_parsed(&self, part: u8, model: &Model) -> Result<Answer, AocError> {
+        return match part {
+            1 => self.part1(model),
+            2 => self.part2(model),
+            _ => Err(AocError::InvalidValue(format!("part {}, expected 1 or 2", part)))
+        };
+    }
+
+    fn solve(&self, part: u8, input: &mut dyn BufRead) -> Result<Answer, AocError> {
+        let model = self.parse(input)?;
+        return self.solve_parsed(part, &model);
+    }
+}
+
+/// Recovers the concrete model a [`Solution`] produced in `parse`.
+pub(crate) fn downcast<M: 'static>(model: &Model) -> Result<&M, AocError> {
+    return model.downcast_ref::<M>()
+        .ok_or_else(|| AocError::InvalidValue(format!("model is not a {}", std::any::type_name::<M>())));
+}
+
+pub static SOLUTIONS: &[&dyn Solution] = &[
+    &day01::Day01,
+    &day02::Day02,
+    &day03::Day03,
+    &day04::Day04,
+    &day05::Day05,
+];
+
+pub fn solution(day: u8) -> Option<&'static dyn Solution> {
+    return SOLUTIONS.iter().find(|s| s.day() == day).copied();
+}
+
+/// Sums `values`, reporting `what` was being computed if the total does not fit in a `u64`.
+pub(crate) fn checked_sum(values: impl IntoIterator<Item = u64>, what: &str) -> Result<u64, AocError> {
+    return values.into_iter()
+        .try_fold(0_u64, |acc, v| acc.checked_add(v))
+        .ok_or_else(|| AocError::InvalidValue(format!("{} overflows u64", what)));
+}
+
+pub fn read_input(input: &mut dyn BufRead) -> Result<Vec<String>, AocError> {
+    return input.lines()
+        .map(|line| line.map_err(AocError::from))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SOLUTIONS, solution, Model};
+
+    #[test]
+    fn solutions_are_registered_once_in_day_order() {
+        let days: Vec<u8> = SOLUTIONS.iter().map(|s| s.day()).collect();
+        assert_eq!(days, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn solution_by_day() {
+        assert_eq!(solution(4).map(|s| s.title()), Some("Scratchcards"));
+        assert!(solution(25).is_none());
+    }
+
+    #[test]
+    fn solve_parsed_model_of_another_day() {
+        let model = solution(2).unwrap().parse(&mut "Game 1: 3 blue".as_bytes()).unwrap();
+        assert_eq!(solution(2).unwrap().solve_parsed(1, &model).unwrap().to_string(), "1");
 
-pub fn read_input(input: &mut dyn BufRead) -> Vec<String> {
-    return input.lines().map(|line| line.unwrap()).collect::<Vec<String>>();
-}
\ No newline at end of file
+        let wrong: Model = Box::new(0_u8);
+        assert!(solution(2).unwrap().solve_parsed(1, &wrong).is_err());
+    }
+}
@@ -1,3 +1,413 @@
-fn main() {
-    println!("Advent of Code 2023");
+#![allow(clippy::needless_return)]
+
+use std::{env, fs::{self, File}, io::{self, BufReader}, path::{Path, PathBuf}, process::ExitCode, time::{Duration, Instant}};
+
+use aoc2023rust::{answers::AnswerBook, bench, day03, day05, fetch::{HttpFetcher, InputCache}, input::InputResolver, solution, Answer, Solution, SOLUTIONS};
+
+const USAGE: &str = "Advent of Code 2023
+
+Usage:
+    aoc2023rust run --day <N> --part <1|2> [--input <PATH|->] [--user <NAME>]
+    aoc2023rust run --all [--user <NAME>]
+    aoc2023rust run --day 5 --trace <CATEGORY>=<VALUE> [--input <PATH|->] [--user <NAME>]
+    aoc2023rust verify [--day <N>] [--answers <PATH>] [--record]
+    aoc2023rust fetch (--day <N> | --all) [--user <NAME>]
+    aoc2023rust bench [--day <N>] [--part <1|2>] [--iterations <N>] [--json] [--user <NAME>]
+    aoc2023rust check [--input <PATH|->] [--user <NAME>]
+    aoc2023rust report [--json | --csv] [--input <PATH|->] [--user <NAME>]
+    aoc2023rust list
+
+Inputs default to $AOC_INPUT_DIR/<user>/dayNN.txt, where the user is --user,
+$AOC_USER or $USER, and fall back to src/dayNN/my.input.
+
+fetch downloads missing inputs into $AOC_INPUT_DIR/<user>/ using the session
+token in $AOC_SESSION; set $AOC_BASE_URL to use a server other than
+https://adventofcode.com.
+
+--trace shows which day 5 map line moves the value at each step to its location.
+
+report lists every day 3 symbol with the numbers around it, numbers that are
+not part numbers and symbols no number touches, as JSON (the default) or CSV.
+
+check reports day 5 map lines that are empty, overflow, or overlap another line.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(()) => return ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(|s| s.as_str()) {
+        Some("run") => return run_solution(&Options::parse(&args[1..])?),
+        Some("verify") => return verify(&Options::parse(&args[1..])?),
+        Some("fetch") => return fetch(&Options::parse(&args[1..])?),
+        Some("bench") => return run_bench(&Options::parse(&args[1..])?),
+        Some("check") => return check(&Options::parse(&args[1..])?),
+        Some("report") => return run_report(&Options::parse(&args[1..])?),
+        Some("list") => {
+            SOLUTIONS.iter().for_each(|s| println!("Day {:>2}: {}", s.day(), s.title()));
+            return Ok(());
+        },
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        Some(command) => return Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+        None => return Err(USAGE.to_string())
+    }
+}
+
+fn run_solution(options: &Options) -> Result<(), String> {
+    let resolver = InputResolver::from_env().with_user(options.user.clone());
+
+    if options.all {
+        return run_all(&resolver);
+    }
+
+    let day = options.day.ok_or("missing --day")?;
+    let path = options.input.clone().unwrap_or_else(|| resolver.puzzle_input(day).display().to_string());
+
+    if let Some(trace) = &options.trace {
+        if day != 5 {
+            return Err("--trace is only supported for day 5".to_string());
+        }
+        return print_trace(&path, trace);
+    }
+
+    let part = options.part.ok_or("missing --part")?;
+    let solution = solution(day).ok_or(format!("no solution for day {}", day))?;
+
+    let answer = if path == "-" {
+        solution.solve(part, &mut io::stdin().lock())
+    }
+    else {
+        let file = File::open(&path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        solution.solve(part, &mut BufReader::new(file))
+    };
+
+    println!("{}", answer.map_err(|e| format!("day{:02} {}", day, e))?);
+    return Ok(());
+}
+
+/// Prints the steps taking `<category>=<value>` to its location as a table.
+fn print_trace(path: &str, trace: &str) -> Result<(), String> {
+    let (category, value) = trace.split_once('=').ok_or(format!("invalid value '{}' for --trace, expected <category>=<value>", trace))?;
+    let value = value.parse::<u64>().map_err(|_| format!("invalid value '{}' for --trace", trace))?;
+
+    let almanac = if path == "-" {
+        day05::parse(&mut io::stdin().lock())
+    }
+    else {
+        let file = File::open(path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        day05::parse(&mut BufReader::new(file))
+    }.map_err(|e| format!("day05 {}", e))?;
+    let steps = almanac.trace(category, "location", value).map_err(|e| format!("day05 {}", e))?;
+
+    let stages: Vec<String> = steps.iter().map(|s| format!("{}-to-{}", s.map.source, s.map.destination)).collect();
+    let lines: Vec<String> = steps.iter().map(|s| s.line.map_or("identity".to_string(), |l| l.to_string())).collect();
+    let stage_width = stages.iter().map(|s| s.len()).max().unwrap_or(0).max("Stage".len());
+    let line_width = lines.iter().map(|l| l.len()).max().unwrap_or(0).max("Line".len());
+
+    println!("{:<stage_width$}  {:>12}  {:<line_width$}  {:>12}", "Stage", "Input", "Line", "Output", stage_width = stage_width, line_width = line_width);
+    for ((step, stage), line) in steps.iter().zip(stages.iter()).zip(lines.iter()) {
+        println!("{:<stage_width$}  {:>12}  {:<line_width$}  {:>12}", stage, step.input, line, step.output, stage_width = stage_width, line_width = line_width);
+    }
+    return Ok(());
+}
+
+struct Measurement {
+    day: u8,
+    part: u8,
+    answer: Result<Answer, String>,
+    parse: Duration,
+    solve: Duration
+}
+
+fn run_all(resolver: &InputResolver) -> Result<(), String> {
+    let measurements: Vec<Measurement> = SOLUTIONS.iter()
+        .flat_map(|&solution| [1, 2].map(|part| measure(solution, part, &resolver.puzzle_input(solution.day()))))
+        .collect();
+
+    print_table(&measurements);
+
+    let failures = measurements.iter().filter(|m| m.answer.is_err()).count();
+    if failures > 0 {
+        return Err(format!("{} of {} solutions failed", failures, measurements.len()));
+    }
+    return Ok(());
+}
+
+/// Times parsing the input separately from solving the part on the parsed model.
+fn measure(solution: &dyn Solution, part: u8, path: &Path) -> Measurement {
+    let mut measurement = Measurement {
+        day: solution.day(),
+        part,
+        answer: Err(String::new()),
+        parse: Duration::ZERO,
+        solve: Duration::ZERO
+    };
+
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(e) => {
+            measurement.answer = Err(format!("cannot open {}: {}", path.display(), e));
+            return measurement;
+        }
+    };
+
+    let start = Instant::now();
+    let model = solution.parse(&mut content.as_slice());
+    measurement.parse = start.elapsed();
+
+    let start = Instant::now();
+    measurement.answer = model
+        .and_then(|model| solution.solve_parsed(part, &model))
+        .map_err(|e| e.to_string());
+    measurement.solve = start.elapsed();
+
+    return measurement;
+}
+
+fn print_table(measurements: &[Measurement]) {
+    let answers: Vec<String> = measurements.iter()
+        .map(|m| match &m.answer {
+            Ok(answer) => answer.to_string().replace('\n', " / "),
+            Err(e) => format!("error: {}", e)
+        })
+        .collect();
+    let width = answers.iter().map(|a| a.len()).max().unwrap_or(0).max("Answer".len());
+
+    println!("{:>3}  {:>4}  {:<width$}  {:>10}  {:>10}", "Day", "Part", "Answer", "Parse", "Solve", width = width);
+    for (m, answer) in measurements.iter().zip(answers.iter()) {
+        println!("{:>3}  {:>4}  {:<width$}  {:>10}  {:>10}", m.day, m.part, answer, format_duration(m.parse), format_duration(m.solve), width = width);
+    }
+
+    let parse: Duration = measurements.iter().map(|m| m.parse).sum();
+    let solve: Duration = measurements.iter().map(|m| m.solve).sum();
+    println!("{:<9}  {:<width$}  {:>10}  {:>10}", "Total", "", format_duration(parse), format_duration(solve), width = width);
+}
+
+fn format_duration(duration: Duration) -> String {
+    return format!("{:.2?}", duration);
+}
+
+const DEFAULT_ANSWERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/answers.toml");
+
+/// Runs every solver on every known input, repository and per-user, and compares against the answer book.
+fn verify(options: &Options) -> Result<(), String> {
+    let answers_path = PathBuf::from(options.answers.as_deref().unwrap_or(DEFAULT_ANSWERS));
+    let mut book = AnswerBook::load(&answers_path).map_err(|e| format!("{}: {}", answers_path.display(), e))?;
+    let resolver = InputResolver::from_env();
+    let (mut passed, mut failed, mut unknown, mut recorded) = (0, 0, 0, 0);
+
+    let solutions = SOLUTIONS.iter().filter(|s| options.day.is_none_or(|day| s.day() == day));
+    for &solution in solutions {
+        for (input, path) in resolver.inputs(solution.day()) {
+            for part in [1, 2] {
+                let label = format!("day{:02} {:<14} part{}", solution.day(), input, part);
+                let answer = fs::read(&path)
+                    .map_err(|e| format!("cannot open {}: {}", path.display(), e))
+                    .and_then(|bytes| solution.solve(part, &mut bytes.as_slice()).map_err(|e| e.to_string()));
+
+                match (book.get(solution.day(), &input, part), answer) {
+                    (Some(expected), Ok(actual)) if *expected == actual => {
+                        passed += 1;
+                        println!("{}  pass     {}", label, actual);
+                    },
+                    (Some(expected), Ok(actual)) => {
+                        failed += 1;
+                        println!("{}  FAIL     expected {}, got {}", label, expected, actual);
+                    },
+                    (Some(expected), Err(e)) => {
+                        failed += 1;
+                        println!("{}  FAIL     expected {}, got error: {}", label, expected, e);
+                    },
+                    (None, Ok(actual)) => {
+                        unknown += 1;
+                        println!("{}  unknown  {}", label, actual);
+                        if options.record {
+                            book.record(solution.day(), &input, part, actual);
+                            recorded += 1;
+                        }
+                    },
+                    (None, Err(e)) => {
+                        unknown += 1;
+                        println!("{}  unknown  error: {}", label, e);
+                    }
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed, {} unknown", passed, failed, unknown);
+
+    if recorded > 0 {
+        book.save(&answers_path).map_err(|e| format!("{}: {}", answers_path.display(), e))?;
+        println!("recorded {} answers in {}", recorded, answers_path.display());
+    }
+
+    if failed > 0 {
+        return Err(format!("{} answers did not match", failed));
+    }
+    return Ok(());
+}
+
+fn fetch(options: &Options) -> Result<(), String> {
+    let days: Vec<u8> = if options.all {
+        SOLUTIONS.iter().map(|s| s.day()).collect()
+    }
+    else {
+        vec![options.day.ok_or("missing --day or --all")?]
+    };
+
+    let resolver = InputResolver::from_env().with_user(options.user.clone());
+    let cache = InputCache::new(&resolver).map_err(|e| e.to_string())?;
+    let pending: Vec<u8> = days.into_iter().filter(|&day| !cache.contains(day)).collect();
+
+    if pending.is_empty() {
+        println!("all inputs already cached");
+        return Ok(());
+    }
+
+    let fetcher = HttpFetcher::from_env().map_err(|e| e.to_string())?;
+    for day in pending {
+        let (path, _) = cache.get_or_fetch(day, &fetcher).map_err(|e| format!("day{:02} {}", day, e))?;
+        println!("day{:02} saved to {}", day, path.display());
+    }
+
+    return Ok(());
+}
+
+/// Validates the day 5 almanac, failing if any map line is suspicious.
+fn check(options: &Options) -> Result<(), String> {
+    let resolver = InputResolver::from_env().with_user(options.user.clone());
+    let path = options.input.clone().unwrap_or_else(|| resolver.puzzle_input(5).display().to_string());
+
+    let issues = if path == "-" {
+        day05::check(&mut io::stdin().lock())
+    }
+    else {
+        let file = File::open(&path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        day05::check(&mut BufReader::new(file))
+    }.map_err(|e| format!("day05 {}", e))?;
+
+    issues.iter().for_each(|issue| println!("{}", issue));
+    if !issues.is_empty() {
+        return Err(format!("{} problems found in {}", issues.len(), path));
+    }
+
+    println!("no problems found in {}", path);
+    return Ok(());
+}
+
+fn run_report(options: &Options) -> Result<(), String> {
+    if options.json && options.csv {
+        return Err("choose one of --json and --csv".to_string());
+    }
+
+    let resolver = InputResolver::from_env().with_user(options.user.clone());
+    let path = options.input.clone().unwrap_or_else(|| resolver.puzzle_input(3).display().to_string());
+
+    let schematic = if path == "-" {
+        day03::parse(&mut io::stdin().lock())
+    }
+    else {
+        let file = File::open(&path).map_err(|e| format!("cannot open {}: {}", path, e))?;
+        day03::parse(&mut BufReader::new(file))
+    }.map_err(|e| format!("day03 {}", e))?;
+
+    let report = day03::report(&schematic);
+    print!("{}", if options.csv { report.to_csv() } else { report.to_json() });
+    return Ok(());
+}
+
+const DEFAULT_ITERATIONS: usize = 10;
+
+fn run_bench(options: &Options) -> Result<(), String> {
+    let resolver = InputResolver::from_env().with_user(options.user.clone());
+    let iterations = options.iterations.unwrap_or(DEFAULT_ITERATIONS);
+    let parts: Vec<u8> = options.part.map_or(vec![1, 2], |part| vec![part]);
+    let mut results = Vec::new();
+
+    let solutions = SOLUTIONS.iter().filter(|s| options.day.is_none_or(|day| s.day() == day));
+    for &solution in solutions {
+        let path = resolver.puzzle_input(solution.day());
+        let input = fs::read(&path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+
+        for &part in parts.iter() {
+            let result = bench::bench(solution, part, &input, iterations)
+                .map_err(|e| format!("day{:02} {}", solution.day(), e))?;
+            if !options.json {
+                println!("day{:02} part{}  min {:>10}  median {:>10}  p95 {:>10}  ({} runs)",
+                    result.day, result.part, format_duration(result.min), format_duration(result.median), format_duration(result.p95), result.iterations);
+            }
+            results.push(result);
+        }
+    }
+
+    if options.json {
+        print!("{}", bench::to_json(&results));
+    }
+    return Ok(());
+}
+
+#[derive(Default)]
+struct Options {
+    all: bool,
+    record: bool,
+    answers: Option<String>,
+    user: Option<String>,
+    iterations: Option<usize>,
+    json: bool,
+    csv: bool,
+    day: Option<u8>,
+    part: Option<u8>,
+    input: Option<String>,
+    trace: Option<String>
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut it = args.iter();
+
+        while let Some(arg) = it.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (arg.as_str(), None)
+            };
+            let mut value = || inline_value.clone()
+                .or_else(|| it.next().cloned())
+                .ok_or(format!("missing value for {}", flag));
+
+            match flag {
+                "--all" | "-a" => options.all = true,
+                "--record" => options.record = true,
+                "--answers" => options.answers = Some(value()?),
+                "--user" | "-u" => options.user = Some(value()?),
+                "--json" => options.json = true,
+                "--csv" => options.csv = true,
+                "--iterations" | "-n" => options.iterations = Some(value()?.parse::<usize>()
+                    .map_err(|_| format!("invalid value for {}", flag))?),
+                "--day" | "-d" => options.day = Some(parse_number(flag, &value()?)?),
+                "--part" | "-p" => options.part = Some(parse_number(flag, &value()?)?),
+                "--input" | "-i" => options.input = Some(value()?),
+                "--trace" => options.trace = Some(value()?),
+                _ => return Err(format!("unknown option '{}'", arg))
+            }
+        }
+
+        return Ok(options);
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u8, String> {
+    return value.parse::<u8>().map_err(|_| format!("invalid value '{}' for {}", value, flag));
 }